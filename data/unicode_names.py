@@ -0,0 +1,60 @@
+#!/usr/bin/env python3
+# Writes src/unicode_names.rs, the character names behind Python's \N{…} escape
+#
+# The names come from the Unicode Character Database built into Python, so run
+# this with a Python whose unicodedata.unidata_version is the one wanted:
+#
+#     python3 data/unicode_names.py
+
+import os, re, unicodedata
+
+OUTPUT = os.path.join( os.path.dirname( os.path.abspath( __file__ ) ), '..', 'src', 'unicode_names.rs' )
+
+names=[]; numbered={}
+for cp in range(0x110000):
+    nm=unicodedata.name(chr(cp),None)
+    if not nm: continue
+    m=re.match(r'(.*-)([0-9A-F]{4,6})$',nm)
+    if m and int(m.group(2),16)==cp:
+        numbered.setdefault(m.group(1),[]).append(cp); continue
+    if nm.startswith('HANGUL SYLLABLE '):
+        continue
+    names.append((nm,cp))
+cands = """NULL NUL;START OF HEADING;SOH;START OF TEXT;STX;END OF TEXT;ETX;END OF TRANSMISSION;EOT;ENQUIRY;ENQ;ACKNOWLEDGE;ACK;ALERT;BEL;BACKSPACE;BS;CHARACTER TABULATION;HORIZONTAL TABULATION;HT;TAB;LINE FEED;NEW LINE;END OF LINE;LF;NL;EOL;LINE TABULATION;VERTICAL TABULATION;VT;FORM FEED;FF;CARRIAGE RETURN;CR;SHIFT OUT;LOCKING-SHIFT ONE;SO;SHIFT IN;LOCKING-SHIFT ZERO;SI;DATA LINK ESCAPE;DLE;DEVICE CONTROL ONE;DC1;DEVICE CONTROL TWO;DC2;DEVICE CONTROL THREE;DC3;DEVICE CONTROL FOUR;DC4;NEGATIVE ACKNOWLEDGE;NAK;SYNCHRONOUS IDLE;SYN;END OF TRANSMISSION BLOCK;ETB;CANCEL;CAN;END OF MEDIUM;EOM;SUBSTITUTE;SUB;ESCAPE;ESC;INFORMATION SEPARATOR FOUR;FILE SEPARATOR;FS;INFORMATION SEPARATOR THREE;GROUP SEPARATOR;GS;INFORMATION SEPARATOR TWO;RECORD SEPARATOR;RS;INFORMATION SEPARATOR ONE;UNIT SEPARATOR;US;SP;DELETE;DEL;PADDING CHARACTER;PAD;HIGH OCTET PRESET;HOP;BREAK PERMITTED HERE;BPH;NO BREAK HERE;NBH;INDEX;IND;NEXT LINE;NEL;START OF SELECTED AREA;SSA;END OF SELECTED AREA;ESA;CHARACTER TABULATION SET;HORIZONTAL TABULATION SET;HTS;CHARACTER TABULATION WITH JUSTIFICATION;HORIZONTAL TABULATION WITH JUSTIFICATION;HTJ;LINE TABULATION SET;VERTICAL TABULATION SET;VTS;PARTIAL LINE FORWARD;PARTIAL LINE DOWN;PLD;PARTIAL LINE BACKWARD;PARTIAL LINE UP;PLU;REVERSE LINE FEED;REVERSE INDEX;RI;SINGLE SHIFT TWO;SINGLE-SHIFT-2;SS2;SINGLE SHIFT THREE;SINGLE-SHIFT-3;SS3;DEVICE CONTROL STRING;DCS;PRIVATE USE ONE;PRIVATE USE-1;PU1;PRIVATE USE TWO;PRIVATE USE-2;PU2;SET TRANSMIT STATE;STS;CANCEL CHARACTER;CCH;MESSAGE WAITING;MW;START OF GUARDED AREA;START OF PROTECTED AREA;SPA;END OF GUARDED AREA;END OF PROTECTED AREA;EPA;START OF STRING;SOS;SINGLE GRAPHIC CHARACTER INTRODUCER;SGC;SINGLE CHARACTER INTRODUCER;SCI;CONTROL SEQUENCE INTRODUCER;CSI;STRING TERMINATOR;ST;OPERATING SYSTEM COMMAND;OSC;PRIVACY MESSAGE;PM;APPLICATION PROGRAM COMMAND;APC;NBSP;SHY;CGJ;ALM;MVS;FVS1;FVS2;FVS3;FVS4;NNBSP;ZWSP;ZWNJ;ZWJ;LRM;RLM;LRE;RLE;PDF;LRO;RLO;WJ;LRI;RLI;FSI;PDI;MMSP;VS1;VS16;BOM;ZWNBSP;BYTE ORDER MARK;SPACE""".replace(" NUL;",";NUL;").split(';')
+known=dict(names)
+aliases=[]
+for c in cands:
+    c=c.strip()
+    try: ch=unicodedata.lookup(c)
+    except KeyError: continue
+    if c in known: continue
+    aliases.append((c,ord(ch)))
+# VS17..VS256
+for i in range(1,257):
+    c='VS%d'%i
+    try: aliases.append((c,ord(unicodedata.lookup(c))))
+    except KeyError: pass
+aliases=sorted(set(aliases))
+# The Tangut ideographs are named by rule NR2 too, though Python's database leaves them out
+numbered['TANGUT IDEOGRAPH-']=list(range(0x17000,0x187F8))+list(range(0x18D00,0x18D09))
+def lit(cp): return "'\\u{%X}'"%cp
+out=[]
+out.append("// The names of the Unicode Character Database %s, generated from UnicodeData.txt and NameAliases.txt\n"%unicodedata.unidata_version)
+out.append("// Hangul syllables are left out, as their names are worked out from their jamo.\n")
+out.append("// Regenerate with data/unicode_names.py; do not edit by hand.\n\n")
+out.append("/// Every character name, sorted by name for binary search\n#[cfg( feature = \"unicode-names\" )]\npub(crate) static UNICODE_NAMES: &[( &str, char )] = &[\n")
+for nm,cp in sorted(names):
+    out.append('\t( "%s", %s ),\n'%(nm,lit(cp)))
+out.append("];\n\n/// Other names for characters, such as `NULL`, `LINE FEED` and `BOM`, sorted by name\npub(crate) static NAME_ALIASES: &[( &str, char )] = &[\n")
+for nm,cp in sorted(aliases):
+    out.append('\t( "%s", %s ),\n'%(nm,lit(cp)))
+out.append("];\n\n/// Names that end in the character’s own codepoint, such as `CJK UNIFIED IDEOGRAPH-4E2D`, and the ranges they cover\npub(crate) static NUMBERED_NAMES: &[( &str, u32, u32 )] = &[\n")
+for p,cps in sorted(numbered.items()):
+    start=prev=cps[0]
+    for cp in cps[1:]+[None]:
+        if cp is not None and cp==prev+1: prev=cp; continue
+        out.append('\t( "%s", 0x%X, 0x%X ),\n'%(p,start,prev))
+        if cp is not None: start=prev=cp
+out.append("];\n")
+open(OUTPUT,'w').write(''.join(out))
+print(len(names),len(aliases))
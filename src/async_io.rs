@@ -0,0 +1,372 @@
+// The streaming adapters again, for tokio's AsyncRead and AsyncWrite
+//
+// They share their state machines with the blocking adapters, so an escape
+// that straddles two reads or writes is handled just the same.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use stream::{Escape, Escaper, Unescape, Unescaper, CHUNK};
+use {EncodeSet, EntitySet};
+
+/// Reads into `buf` from `inner`, through `unescaper`
+fn poll_read_through<R: AsyncRead + Unpin>( inner: &mut R, unescaper: &mut Unescaper, cx: &mut Context, buf: &mut ReadBuf ) -> Poll<io::Result<( )>> {
+	loop {
+		if let Some( length ) = unescaper.take( buf.initialize_unfilled( ) ) {
+			buf.advance( length );
+			return Poll::Ready( Ok( ( ) ) );
+		}
+
+		let mut chunk = [ 0; CHUNK ];
+		let mut chunk = ReadBuf::new( &mut chunk );
+		match Pin::new( &mut *inner ).poll_read( cx, &mut chunk ) {
+			Poll::Ready( Ok( ( ) ) ) => unescaper.feed( chunk.filled( ) ),
+			Poll::Ready( Err( error ) ) => return Poll::Ready( Err( error ) ),
+			Poll::Pending => return Poll::Pending,
+		}
+	}
+}
+
+/// Decodes HTML/XML entities from an async reader, as `EntityDecoder` does
+pub struct AsyncEntityDecoder<'s, R> {
+	inner: R,
+	unescaper: Unescaper<'s>,
+}
+
+impl<'s, R: AsyncRead + Unpin> AsyncEntityDecoder<'s, R> {
+	/// Decodes the names `decode_entities` knows
+	pub fn new( inner: R ) -> AsyncEntityDecoder<'s, R> {
+		AsyncEntityDecoder::with_set( inner, EntitySet::Xhtml1 )
+	}
+
+	/// Decodes only the names in `set`, which are read as `EntityDecoder::with_set` reads them
+	pub fn with_set( inner: R, set: EntitySet<'s> ) -> AsyncEntityDecoder<'s, R> {
+		AsyncEntityDecoder { inner, unescaper: Unescaper::new( Unescape::Entities( set ) ) }
+	}
+
+	/// Gives back the inner reader
+	pub fn into_inner( self ) -> R {
+		self.inner
+	}
+}
+
+impl<'s, R: AsyncRead + Unpin> AsyncRead for AsyncEntityDecoder<'s, R> {
+	fn poll_read( self: Pin<&mut Self>, cx: &mut Context, buf: &mut ReadBuf ) -> Poll<io::Result<( )>> {
+		let this = self.get_mut( );
+		poll_read_through( &mut this.inner, &mut this.unescaper, cx, buf )
+	}
+}
+
+/// Decodes percent escapes from an async reader, as `PercentDecoder` does
+pub struct AsyncPercentDecoder<R> {
+	inner: R,
+	unescaper: Unescaper<'static>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncPercentDecoder<R> {
+	pub fn new( inner: R ) -> AsyncPercentDecoder<R> {
+		AsyncPercentDecoder { inner, unescaper: Unescaper::new( Unescape::Percent ) }
+	}
+
+	/// Gives back the inner reader
+	pub fn into_inner( self ) -> R {
+		self.inner
+	}
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncPercentDecoder<R> {
+	fn poll_read( self: Pin<&mut Self>, cx: &mut Context, buf: &mut ReadBuf ) -> Poll<io::Result<( )>> {
+		let this = self.get_mut( );
+		poll_read_through( &mut this.inner, &mut this.unescaper, cx, buf )
+	}
+}
+
+/// Decodes a quoted-printable body from an async reader, as `QuotedPrintableDecoder` does
+pub struct AsyncQuotedPrintableDecoder<R> {
+	inner: R,
+	unescaper: Unescaper<'static>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncQuotedPrintableDecoder<R> {
+	pub fn new( inner: R ) -> AsyncQuotedPrintableDecoder<R> {
+		AsyncQuotedPrintableDecoder { inner, unescaper: Unescaper::new( Unescape::QuotedPrintable ) }
+	}
+
+	/// Gives back the inner reader
+	pub fn into_inner( self ) -> R {
+		self.inner
+	}
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncQuotedPrintableDecoder<R> {
+	fn poll_read( self: Pin<&mut Self>, cx: &mut Context, buf: &mut ReadBuf ) -> Poll<io::Result<( )>> {
+		let this = self.get_mut( );
+		poll_read_through( &mut this.inner, &mut this.unescaper, cx, buf )
+	}
+}
+
+/// Encodes into an async writer, queueing what the writer has not yet taken
+struct Outgoing<'s, W> {
+	inner: W,
+	escaper: Escaper<'s>,
+	queued: Vec<u8>,
+	finished: bool,
+}
+
+impl<'s, W: AsyncWrite + Unpin> Outgoing<'s, W> {
+	fn new( inner: W, escape: Escape<'s> ) -> Outgoing<'s, W> {
+		Outgoing { inner, escaper: Escaper::new( escape ), queued: Vec::new( ), finished: false }
+	}
+
+	/// Writes out what is queued
+	fn poll_drain( &mut self, cx: &mut Context ) -> Poll<io::Result<( )>> {
+		while !self.queued.is_empty( ) {
+			match Pin::new( &mut self.inner ).poll_write( cx, &self.queued ) {
+				Poll::Ready( Ok( 0 ) ) => return Poll::Ready( Err( io::ErrorKind::WriteZero.into( ) ) ),
+				Poll::Ready( Ok( length ) ) => { self.queued.drain( ..length ); },
+				Poll::Ready( Err( error ) ) => return Poll::Ready( Err( error ) ),
+				Poll::Pending => return Poll::Pending,
+			}
+		}
+		Poll::Ready( Ok( ( ) ) )
+	}
+
+	fn poll_write( &mut self, cx: &mut Context, buf: &[u8] ) -> Poll<io::Result<usize>> {
+		match self.poll_drain( cx ) {
+			Poll::Ready( Ok( ( ) ) ) => { },
+			Poll::Ready( Err( error ) ) => return Poll::Ready( Err( error ) ),
+			Poll::Pending => return Poll::Pending,
+		}
+		self.queued = self.escaper.encode( buf, false );
+		Poll::Ready( Ok( buf.len( ) ) )
+	}
+
+	fn poll_flush( &mut self, cx: &mut Context ) -> Poll<io::Result<( )>> {
+		match self.poll_drain( cx ) {
+			Poll::Ready( Ok( ( ) ) ) => Pin::new( &mut self.inner ).poll_flush( cx ),
+			other => other,
+		}
+	}
+
+	/// Writes out anything held back as well, then shuts the inner writer down
+	fn poll_shutdown( &mut self, cx: &mut Context ) -> Poll<io::Result<( )>> {
+		if !self.finished {
+			let held = self.escaper.encode( &[ ], true );
+			self.queued.extend_from_slice( &held );
+			self.finished = true;
+		}
+		match self.poll_drain( cx ) {
+			Poll::Ready( Ok( ( ) ) ) => Pin::new( &mut self.inner ).poll_shutdown( cx ),
+			other => other,
+		}
+	}
+}
+
+/// Encodes the markup-significant characters written to it as entities, as `EntityEncoder` does
+pub struct AsyncEntityEncoder<'s, W> {
+	outgoing: Outgoing<'s, W>,
+}
+
+impl<'s, W: AsyncWrite + Unpin> AsyncEntityEncoder<'s, W> {
+	/// Encodes as `encode_entities` does
+	pub fn new( inner: W ) -> AsyncEntityEncoder<'s, W> {
+		AsyncEntityEncoder::with_set( inner, EntitySet::Html4 )
+	}
+
+	/// Encodes with the names in `set`
+	pub fn with_set( inner: W, set: EntitySet<'s> ) -> AsyncEntityEncoder<'s, W> {
+		AsyncEntityEncoder { outgoing: Outgoing::new( inner, Escape::Entities( set ) ) }
+	}
+
+	/// Gives back the inner writer; flush or shut down first, or what is queued is lost
+	pub fn into_inner( self ) -> W {
+		self.outgoing.inner
+	}
+}
+
+impl<'s, W: AsyncWrite + Unpin> AsyncWrite for AsyncEntityEncoder<'s, W> {
+	fn poll_write( self: Pin<&mut Self>, cx: &mut Context, buf: &[u8] ) -> Poll<io::Result<usize>> {
+		self.get_mut( ).outgoing.poll_write( cx, buf )
+	}
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context ) -> Poll<io::Result<( )>> {
+		self.get_mut( ).outgoing.poll_flush( cx )
+	}
+
+	fn poll_shutdown( self: Pin<&mut Self>, cx: &mut Context ) -> Poll<io::Result<( )>> {
+		self.get_mut( ).outgoing.poll_shutdown( cx )
+	}
+}
+
+/// Percent-encodes what is written to it, as `PercentEncoder` does
+///
+/// Anything held back is written out on shutdown.
+pub struct AsyncPercentEncoder<W> {
+	outgoing: Outgoing<'static, W>,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncPercentEncoder<W> {
+	pub fn new( inner: W, set: EncodeSet ) -> AsyncPercentEncoder<W> {
+		AsyncPercentEncoder { outgoing: Outgoing::new( inner, Escape::Percent( set ) ) }
+	}
+
+	/// Gives back the inner writer; shut down first, or what is held back is lost
+	pub fn into_inner( self ) -> W {
+		self.outgoing.inner
+	}
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncPercentEncoder<W> {
+	fn poll_write( self: Pin<&mut Self>, cx: &mut Context, buf: &[u8] ) -> Poll<io::Result<usize>> {
+		self.get_mut( ).outgoing.poll_write( cx, buf )
+	}
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context ) -> Poll<io::Result<( )>> {
+		self.get_mut( ).outgoing.poll_flush( cx )
+	}
+
+	fn poll_shutdown( self: Pin<&mut Self>, cx: &mut Context ) -> Poll<io::Result<( )>> {
+		self.get_mut( ).outgoing.poll_shutdown( cx )
+	}
+}
+
+/// Encodes what is written to it as a quoted-printable body, as `QuotedPrintableEncoder` does
+///
+/// Anything held back is written out on shutdown.
+pub struct AsyncQuotedPrintableEncoder<W> {
+	outgoing: Outgoing<'static, W>,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncQuotedPrintableEncoder<W> {
+	pub fn new( inner: W ) -> AsyncQuotedPrintableEncoder<W> {
+		AsyncQuotedPrintableEncoder { outgoing: Outgoing::new( inner, Escape::QuotedPrintable ) }
+	}
+
+	/// Gives back the inner writer; shut down first, or what is held back is lost
+	pub fn into_inner( self ) -> W {
+		self.outgoing.inner
+	}
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncQuotedPrintableEncoder<W> {
+	fn poll_write( self: Pin<&mut Self>, cx: &mut Context, buf: &[u8] ) -> Poll<io::Result<usize>> {
+		self.get_mut( ).outgoing.poll_write( cx, buf )
+	}
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context ) -> Poll<io::Result<( )>> {
+		self.get_mut( ).outgoing.poll_flush( cx )
+	}
+
+	fn poll_shutdown( self: Pin<&mut Self>, cx: &mut Context ) -> Poll<io::Result<( )>> {
+		self.get_mut( ).outgoing.poll_shutdown( cx )
+	}
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+	use std::cmp;
+	use std::task::Waker;
+
+	/// Hands out a few bytes at a time, and is not ready every other time it is asked
+	struct Trickle<'a>( &'a [u8], usize, bool );
+
+	impl<'a> AsyncRead for Trickle<'a> {
+		fn poll_read( self: Pin<&mut Self>, cx: &mut Context, buf: &mut ReadBuf ) -> Poll<io::Result<( )>> {
+			let this = self.get_mut( );
+			this.2 = !this.2;
+			if this.2 {
+				cx.waker( ).wake_by_ref( );
+				return Poll::Pending;
+			}
+			let length = cmp::min( cmp::min( this.1, buf.remaining( ) ), this.0.len( ) );
+			buf.put_slice( &this.0[ ..length ] );
+			this.0 = &this.0[ length..];
+			Poll::Ready( Ok( ( ) ) )
+		}
+	}
+
+	/// Takes a few bytes at a time, and is not ready every other time it is asked
+	#[derive( Default )]
+	struct Choke( Vec<u8>, bool );
+
+	impl AsyncWrite for Choke {
+		fn poll_write( self: Pin<&mut Self>, cx: &mut Context, buf: &[u8] ) -> Poll<io::Result<usize>> {
+			let this = self.get_mut( );
+			this.1 = !this.1;
+			if this.1 {
+				cx.waker( ).wake_by_ref( );
+				return Poll::Pending;
+			}
+			let length = cmp::min( 3, buf.len( ) );
+			this.0.extend_from_slice( &buf[ ..length ] );
+			Poll::Ready( Ok( length ) )
+		}
+
+		fn poll_flush( self: Pin<&mut Self>, _: &mut Context ) -> Poll<io::Result<( )>> {
+			Poll::Ready( Ok( ( ) ) )
+		}
+
+		fn poll_shutdown( self: Pin<&mut Self>, _: &mut Context ) -> Poll<io::Result<( )>> {
+			Poll::Ready( Ok( ( ) ) )
+		}
+	}
+
+	fn read_all<R: AsyncRead + Unpin>( mut reader: R ) -> Vec<u8> {
+		let mut cx = Context::from_waker( Waker::noop( ) );
+		let mut allo = Vec::new( );
+		loop {
+			let mut buf = [ 0; 5 ];
+			let mut buf = ReadBuf::new( &mut buf );
+			match Pin::new( &mut reader ).poll_read( &mut cx, &mut buf ) {
+				Poll::Ready( Ok( ( ) ) ) if buf.filled( ).is_empty( ) => return allo,
+				Poll::Ready( Ok( ( ) ) ) => allo.extend_from_slice( buf.filled( ) ),
+				Poll::Ready( Err( error ) ) => panic!( "{}", error ),
+				Poll::Pending => { }
+			}
+		}
+	}
+
+	fn write_all<W: AsyncWrite + Unpin>( writer: &mut W, mut data: &[u8] ) {
+		let mut cx = Context::from_waker( Waker::noop( ) );
+		while !data.is_empty( ) {
+			if let Poll::Ready( written ) = Pin::new( &mut *writer ).poll_write( &mut cx, &data[ ..cmp::min( 2, data.len( ) ) ] ) {
+				data = &data[ written.unwrap( )..];
+			}
+		}
+		while Pin::new( &mut *writer ).poll_shutdown( &mut cx ).is_pending( ) { }
+	}
+
+	#[test]
+	fn test_async_decoders( ) {
+
+		for step in 1..6 {
+			assert_eq!( read_all( AsyncEntityDecoder::new( Trickle( b"&lt;&hearts;&#x2665;&#9829;&amp;amp;&fred;", step, false ) ) ),	"<♥♥♥&amp;&fred;".as_bytes( ) );
+			assert_eq!( read_all( AsyncPercentDecoder::new( Trickle( b"%3Fa%3d%2F%zz%4%", step, false ) ) ),	b"?a=/%zz%4%" );
+			assert_eq!( read_all( AsyncQuotedPrintableDecoder::new( Trickle( b"a=3Db =\r\nc=\nd=0D=0A=", step, false ) ) ),	b"a=b cd\r\n=" );
+		}
+		assert_eq!( read_all( AsyncEntityDecoder::with_set( &b"&nbsp;&apos;"[ ..], EntitySet::Xml ) ),	b"&nbsp;'" );
+		let names = vec![ ( "fred", "Fred" ) ];
+		assert_eq!( read_all( AsyncEntityDecoder::with_set( &b"&fred;&amp;"[ ..], EntitySet::Custom( &names ) ) ),	b"Fred&amp;" );
+	}
+
+	#[test]
+	fn test_async_encoders( ) {
+
+		let mut encoder = AsyncEntityEncoder::new( Choke::default( ) );
+		write_all( &mut encoder, b"'Fish' & <Chips>" );
+		assert_eq!( encoder.into_inner( ).0,	&b"&#x27;Fish&#x27; &amp; &lt;Chips&gt;"[ ..] );
+
+		let mut encoder = AsyncPercentEncoder::new( Choke::default( ), EncodeSet::Url );
+		write_all( &mut encoder, b"/%41%zz %" );
+		assert_eq!( encoder.into_inner( ).0,	&b"/%41%25zz%20%25"[ ..] );
+
+		let mut encoder = AsyncQuotedPrintableEncoder::new( Choke::default( ) );
+		write_all( &mut encoder, b"tab\t \r\nnull\x00 end \r" );
+		assert_eq!( encoder.into_inner( ).0,	&b"tab\t=20\r\nnull=00 end=20=0D"[ ..] );
+	}
+}
@@ -0,0 +1,86 @@
+// Base16, better known as hex
+// See: The Base16, Base32, and Base64 Data Encodings https://tools.ietf.org/html/rfc4648#section-8
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg( feature = "std" )]
+use charset::decode_charset;
+use error::DecodeError;
+
+/// Decodes hex into the bytes it carries, accepting either case
+///
+/// # Examples
+///
+/// ```
+/// use mung::decode_base16_bytes;
+/// assert_eq!( decode_base16_bytes( b"DEADbeef" ), Ok( vec![ 0xDE, 0xAD, 0xBE, 0xEF ] ) );
+/// ```
+pub fn decode_base16_bytes( s: &[u8] ) -> Result<Vec<u8>, DecodeError> {
+
+	if !s.len( ).is_multiple_of( 2 ) {
+		return Err( DecodeError::InvalidLength { length: s.len( ) } );
+	}
+
+	let nibble = |offset: usize| {
+		let byte = s[ offset ];
+		( byte as char ).to_digit( 16 ).map( |value| value as u8 ).ok_or( DecodeError::InvalidByte { offset, byte } )
+	};
+
+	( 0..s.len( ) / 2 ).map( |i| Ok( nibble( 2 * i )? << 4 | nibble( 2 * i + 1 )? ) ).collect( )
+}
+
+/// Decodes hex into text, reading the carried bytes in the named charset
+///
+/// # Examples
+///
+/// ```
+/// use mung::decode_base16;
+/// assert_eq!( decode_base16( "416E6472E9", "latin1" ).unwrap( ), "André" );
+/// ```
+#[cfg( feature = "std" )]
+pub fn decode_base16( s: &str, charset: &str ) -> Result<String, DecodeError> {
+	decode_base16_bytes( s.as_bytes( ) ).map( |bytes| decode_charset( &bytes, charset ).into_owned( ) )
+}
+
+/// Encodes bytes as upper case hex, as RFC 4648 writes it
+///
+/// # Examples
+///
+/// ```
+/// use mung::encode_base16;
+/// assert_eq!( encode_base16( b"\xDE\xAD" ), "DEAD" );
+/// ```
+pub fn encode_base16( bytes: &[u8] ) -> String {
+
+	static DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+	let mut allo = String::with_capacity( bytes.len( ) * 2 );
+	for &byte in bytes {
+		allo.push( DIGITS[ ( byte >> 4 ) as usize ] as char );
+		allo.push( DIGITS[ ( byte & 0xF ) as usize ] as char );
+	}
+	allo
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_base16( ) {
+
+		assert_eq!( decode_base16_bytes( b"" ),					Ok( vec![ ] ) );
+		assert_eq!( decode_base16_bytes( b"666F6F626172" ),		Ok( b"foobar".to_vec( ) ) );
+		assert_eq!( decode_base16_bytes( b"666f6f626172" ),		Ok( b"foobar".to_vec( ) ) );
+		assert_eq!( decode_base16_bytes( b"666" ),				Err( DecodeError::InvalidLength { length: 3 } ) );
+		assert_eq!( decode_base16_bytes( b"66 6" ),				Err( DecodeError::InvalidByte { offset: 2, byte: b' ' } ) );
+		assert_eq!( decode_base16_bytes( b"6g" ),				Err( DecodeError::InvalidByte { offset: 1, byte: b'g' } ) );
+
+		#[cfg( feature = "std" )]
+		assert_eq!( decode_base16( "E282AC", "utf-8" ),			Ok( "€".to_string( ) ) );
+		assert_eq!( encode_base16( b"foobar" ),					"666F6F626172" );
+		assert_eq!( encode_base16( &[ 0, 255 ] ),				"00FF" );
+	}
+}
@@ -0,0 +1,192 @@
+// Base32, as found in TOTP secrets, onion addresses and DNSSEC
+// See: The Base16, Base32, and Base64 Data Encodings https://tools.ietf.org/html/rfc4648#section-6
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg( feature = "std" )]
+use charset::decode_charset;
+use error::DecodeError;
+
+static STANDARD_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+static HEX_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+/// Which base32 to speak
+#[derive( Clone, Copy, Debug, PartialEq, Eq )]
+pub enum Base32 {
+	/// RFC 4648 §6: `A-Z2-7` alphabet, `=` padding required
+	Standard,
+	/// `A-Z2-7` alphabet without padding, as TOTP secrets are usually written
+	Unpadded,
+	/// RFC 4648 §7 “base32hex”: `0-9A-V` alphabet, `=` padding required
+	Hex,
+	/// `0-9A-V` alphabet without padding, as in DNSSEC NSEC3 records
+	HexUnpadded,
+}
+
+impl Base32 {
+	fn hex( self ) -> bool {
+		self == Base32::Hex || self == Base32::HexUnpadded
+	}
+
+	fn padded( self ) -> bool {
+		self == Base32::Standard || self == Base32::Hex
+	}
+}
+
+/// The five bits a base32 symbol stands for; either case is accepted
+fn quintet( byte: u8, hex: bool ) -> Option<u8> {
+	match byte.to_ascii_uppercase( ) {
+		b'A' ..= b'Z' if !hex => Some( byte.to_ascii_uppercase( ) - b'A' ),
+		b'2' ..= b'7' if !hex => Some( byte - b'2' + 26 ),
+		b'0' ..= b'9' if hex => Some( byte - b'0' ),
+		b'A' ..= b'V' if hex => Some( byte.to_ascii_uppercase( ) - b'A' + 10 ),
+		_ => None
+	}
+}
+
+/// Decodes base32 into the bytes it carries
+///
+/// Lower case is accepted, since secrets are often typed that way, but
+/// whitespace is not; strip the spaces from a grouped TOTP secret first.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{decode_base32_bytes, Base32};
+/// assert_eq!( decode_base32_bytes( b"MZXW6YTBOI======", Base32::Standard ), Ok( b"foobar".to_vec( ) ) );
+/// assert_eq!( decode_base32_bytes( b"mzxw6ytboi", Base32::Unpadded ), Ok( b"foobar".to_vec( ) ) );
+/// ```
+pub fn decode_base32_bytes( s: &[u8], variant: Base32 ) -> Result<Vec<u8>, DecodeError> {
+
+	let hex = variant.hex( );
+	let mut allo = Vec::with_capacity( s.len( ) * 5 / 8 );
+	let mut accumulated = 0u64;
+	let mut bits = 0u32;
+	let mut symbols = 0usize;
+	let mut last_symbol = 0usize;
+	let mut padding = 0usize;
+	let mut padding_at = None;
+
+	for ( offset, &byte ) in s.iter( ).enumerate( ) {
+
+		if byte == b'=' {
+			if !variant.padded( ) {
+				return Err( DecodeError::InvalidPadding { offset } );
+			}
+			padding_at = padding_at.or( Some( offset ) );
+			padding += 1;
+			continue;
+		}
+
+		let value = match quintet( byte, hex ) {
+			Some( value ) => value,
+			None => return Err( DecodeError::InvalidByte { offset, byte } ),
+		};
+		if let Some( at ) = padding_at {
+			return Err( DecodeError::InvalidPadding { offset: at } );
+		}
+
+		accumulated = accumulated << 5 | u64::from( value );
+		bits += 5;
+		symbols += 1;
+		last_symbol = offset;
+		if bits >= 8 {
+			bits -= 8;
+			allo.push( ( accumulated >> bits ) as u8 );
+			accumulated &= ( 1 << bits ) - 1;
+		}
+	}
+
+	// Only these many symbols can end a group of eight
+	let expected_padding = match symbols % 8 {
+		0 => 0,
+		2 => 6,
+		4 => 4,
+		5 => 3,
+		7 => 1,
+		_ => return Err( DecodeError::InvalidLength { length: symbols + padding } ),
+	};
+	if variant.padded( ) && padding != expected_padding {
+		return Err( DecodeError::InvalidPadding { offset: padding_at.unwrap_or( s.len( ) ) } );
+	}
+	if accumulated != 0 {
+		return Err( DecodeError::TrailingBits { offset: last_symbol } );
+	}
+
+	Ok( allo )
+}
+
+/// Decodes base32 into text, reading the carried bytes in the named charset
+#[cfg( feature = "std" )]
+pub fn decode_base32( s: &str, variant: Base32, charset: &str ) -> Result<String, DecodeError> {
+	decode_base32_bytes( s.as_bytes( ), variant ).map( |bytes| decode_charset( &bytes, charset ).into_owned( ) )
+}
+
+/// Encodes bytes as upper case base32
+///
+/// # Examples
+///
+/// ```
+/// use mung::{encode_base32, Base32};
+/// assert_eq!( encode_base32( b"foobar", Base32::Standard ), "MZXW6YTBOI======" );
+/// assert_eq!( encode_base32( b"foobar", Base32::HexUnpadded ), "CPNMUOJ1E8" );
+/// ```
+pub fn encode_base32( bytes: &[u8], variant: Base32 ) -> String {
+
+	let alphabet = if variant.hex( ) { HEX_ALPHABET } else { STANDARD_ALPHABET };
+	let mut allo = String::with_capacity( bytes.len( ).div_ceil( 5 ) * 8 );
+
+	for chunk in bytes.chunks( 5 ) {
+		let group = chunk.iter( ).enumerate( ).fold( 0u64, |group, ( i, &byte )| group | u64::from( byte ) << ( 32 - 8 * i ) );
+		let symbols = ( chunk.len( ) * 8 ).div_ceil( 5 );
+
+		for i in 0..8 {
+			if i < symbols {
+				allo.push( alphabet[ ( group >> ( 35 - 5 * i ) & 0x1F ) as usize ] as char );
+			} else if variant.padded( ) {
+				allo.push( '=' );
+			}
+		}
+	}
+
+	allo
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_base32( ) {
+
+		// RFC 4648 §10 test vectors
+		for &( plain, standard, hex ) in &[ ( "", "", "" ), ( "f", "MY======", "CO======" ), ( "fo", "MZXQ====", "CPNG====" ),
+				( "foo", "MZXW6===", "CPNMU===" ), ( "foob", "MZXW6YQ=", "CPNMUOG=" ), ( "fooba", "MZXW6YTB", "CPNMUOJ1" ),
+				( "foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======" ) ] {
+			assert_eq!( encode_base32( plain.as_bytes( ), Base32::Standard ), standard );
+			assert_eq!( encode_base32( plain.as_bytes( ), Base32::Hex ), hex );
+			assert_eq!( decode_base32_bytes( standard.as_bytes( ), Base32::Standard ), Ok( plain.as_bytes( ).to_vec( ) ) );
+			assert_eq!( decode_base32_bytes( hex.as_bytes( ), Base32::Hex ), Ok( plain.as_bytes( ).to_vec( ) ) );
+			assert_eq!( decode_base32_bytes( standard.trim_end_matches( '=' ).as_bytes( ), Base32::Unpadded ), Ok( plain.as_bytes( ).to_vec( ) ) );
+			assert_eq!( decode_base32_bytes( hex.trim_end_matches( '=' ).as_bytes( ), Base32::HexUnpadded ), Ok( plain.as_bytes( ).to_vec( ) ) );
+		}
+
+		// A TOTP secret as an authenticator app shows it
+		assert_eq!( decode_base32_bytes( b"jbswy3dpehpk3pxp", Base32::Unpadded ),	Ok( b"Hello!\xDE\xAD\xBE\xEF".to_vec( ) ) );
+		#[cfg( feature = "std" )]
+		assert_eq!( decode_base32( "JBSWY3DP", Base32::Unpadded, "utf-8" ),		Ok( "Hello".to_string( ) ) );
+	}
+
+	#[test]
+	fn test_base32_failure( ) {
+
+		assert_eq!( decode_base32_bytes( b"MZXW1===", Base32::Standard ),		Err( DecodeError::InvalidByte { offset: 4, byte: b'1' } ) );
+		assert_eq!( decode_base32_bytes( b"MZXW6", Base32::Standard ),			Err( DecodeError::InvalidPadding { offset: 5 } ) );
+		assert_eq!( decode_base32_bytes( b"MZXW6===", Base32::Unpadded ),		Err( DecodeError::InvalidPadding { offset: 5 } ) );
+		assert_eq!( decode_base32_bytes( b"MZX", Base32::Unpadded ),			Err( DecodeError::InvalidLength { length: 3 } ) );
+		assert_eq!( decode_base32_bytes( b"MZ======MZ======", Base32::Standard ),	Err( DecodeError::InvalidPadding { offset: 2 } ) );
+		assert_eq!( decode_base32_bytes( b"MZ", Base32::Unpadded ),			Err( DecodeError::TrailingBits { offset: 1 } ) );
+	}
+}
@@ -0,0 +1,272 @@
+// Base64, in the flavours that turn up in mail, URLs and tokens
+// See: The Base16, Base32, and Base64 Data Encodings https://tools.ietf.org/html/rfc4648
+// See also: MIME Part One https://tools.ietf.org/html/rfc2045#section-6.8
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg( feature = "std" )]
+use charset::decode_charset;
+use error::DecodeError;
+
+static STANDARD_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+static URL_SAFE_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Which base64 to speak
+#[derive( Clone, Copy, Debug, PartialEq, Eq )]
+pub enum Base64 {
+	/// RFC 4648 §4: `+/` alphabet, `=` padding required, nothing else allowed
+	Standard,
+	/// RFC 4648 §5: `-_` alphabet, `=` padding required
+	UrlSafe,
+	/// RFC 2045: `+/` alphabet in lines of 76, whitespace ignored and padding optional when decoding
+	Mime,
+	/// `+/` alphabet without padding, as in many JSON and crypt formats
+	Unpadded,
+	/// `-_` alphabet without padding, as in JWTs
+	UrlSafeUnpadded,
+}
+
+impl Base64 {
+	fn url_safe( self ) -> bool {
+		self == Base64::UrlSafe || self == Base64::UrlSafeUnpadded
+	}
+
+	fn padded( self ) -> bool {
+		self == Base64::Standard || self == Base64::UrlSafe || self == Base64::Mime
+	}
+}
+
+/// The six bits a base64 symbol stands for
+fn sextet( byte: u8, url_safe: bool ) -> Option<u8> {
+	match byte {
+		b'A' ..= b'Z' => Some( byte - b'A' ),
+		b'a' ..= b'z' => Some( byte - b'a' + 26 ),
+		b'0' ..= b'9' => Some( byte - b'0' + 52 ),
+		b'+' if !url_safe => Some( 62 ),
+		b'/' if !url_safe => Some( 63 ),
+		b'-' if url_safe => Some( 62 ),
+		b'_' if url_safe => Some( 63 ),
+		_ => None
+	}
+}
+
+/// Decodes base64 into the bytes it carries, without any charset decoding
+///
+/// Errors say exactly which byte was at fault, so a caller can report it or
+/// fall back to showing the original, as RFC 2047 §6.2 asks.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{decode_base64_bytes, Base64, DecodeError};
+/// assert_eq!( decode_base64_bytes( b"3q2-7w", Base64::UrlSafeUnpadded ), Ok( vec![ 0xDE, 0xAD, 0xBE, 0xEF ] ) );
+/// let strict = decode_base64_bytes( b"3q2-7w==", Base64::Standard );
+/// assert_eq!( strict, Err( DecodeError::InvalidByte { offset: 3, byte: b'-' } ) );
+/// ```
+pub fn decode_base64_bytes( s: &[u8], variant: Base64 ) -> Result<Vec<u8>, DecodeError> {
+
+	let url_safe = variant.url_safe( );
+	let mut allo = Vec::with_capacity( s.len( ) / 4 * 3 );
+	let mut accumulated = 0u32;
+	let mut symbols = 0usize;
+	let mut last_symbol = 0usize;
+	let mut padding = 0usize;
+	let mut padding_at = None;
+
+	for ( offset, &byte ) in s.iter( ).enumerate( ) {
+
+		if variant == Base64::Mime && ( byte == b' ' || byte == b'\t' || byte == b'\r' || byte == b'\n' ) {
+			continue;
+		}
+
+		if byte == b'=' {
+			if !variant.padded( ) {
+				return Err( DecodeError::InvalidPadding { offset } );
+			}
+			padding_at = padding_at.or( Some( offset ) );
+			padding += 1;
+			continue;
+		}
+
+		let value = match sextet( byte, url_safe ) {
+			Some( value ) => value,
+			None => return Err( DecodeError::InvalidByte { offset, byte } ),
+		};
+		if let Some( at ) = padding_at {
+			// symbols after padding
+			return Err( DecodeError::InvalidPadding { offset: at } );
+		}
+
+		accumulated = accumulated << 6 | u32::from( value );
+		symbols += 1;
+		last_symbol = offset;
+		if symbols.is_multiple_of( 4 ) {
+			allo.push( ( accumulated >> 16 ) as u8 );
+			allo.push( ( accumulated >> 8 ) as u8 );
+			allo.push( accumulated as u8 );
+			accumulated = 0;
+		}
+	}
+
+	let remainder = symbols % 4;
+	if remainder == 1 {
+		return Err( DecodeError::InvalidLength { length: symbols + padding } );
+	}
+
+	let expected_padding = ( 4 - remainder ) % 4;
+	let padding_ok = match variant {
+		Base64::Mime => padding == 0 || padding == expected_padding,
+		_ if variant.padded( ) => padding == expected_padding,
+		_ => true,
+	};
+	if !padding_ok {
+		return Err( DecodeError::InvalidPadding { offset: padding_at.unwrap_or( s.len( ) ) } );
+	}
+
+	// A canonical encoder leaves the unused low bits zero; MIME is too old to insist
+	let spare_bits = match remainder {
+		2 => 4,
+		3 => 2,
+		_ => 0,
+	};
+	if variant != Base64::Mime && accumulated & ( ( 1 << spare_bits ) - 1 ) != 0 {
+		return Err( DecodeError::TrailingBits { offset: last_symbol } );
+	}
+	match remainder {
+		2 => {
+			allo.push( ( accumulated >> 4 ) as u8 );
+		},
+		3 => {
+			allo.push( ( accumulated >> 10 ) as u8 );
+			allo.push( ( accumulated >> 2 ) as u8 );
+		},
+		_ => { }
+	}
+
+	Ok( allo )
+}
+
+/// Decodes base64 into text, reading the carried bytes in the named charset
+///
+/// # Examples
+///
+/// ```
+/// use mung::{decode_base64, Base64};
+/// let subject = decode_base64( "s8m5prXEsvrGt76twO0=", Base64::Standard, "GB2312" );
+/// assert_eq!( subject.unwrap( ), "成功的产品经理" );
+/// ```
+#[cfg( feature = "std" )]
+pub fn decode_base64( s: &str, variant: Base64, charset: &str ) -> Result<String, DecodeError> {
+	decode_base64_bytes( s.as_bytes( ), variant ).map( |bytes| decode_charset( &bytes, charset ).into_owned( ) )
+}
+
+/// Encodes bytes as base64
+///
+/// To encode text, pass its bytes in the charset it should travel in;
+/// `s.as_bytes( )` for UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{encode_base64, Base64};
+/// assert_eq!( encode_base64( b"test", Base64::Standard ), "dGVzdA==" );
+/// assert_eq!( encode_base64( b"test", Base64::Unpadded ), "dGVzdA" );
+/// ```
+pub fn encode_base64( bytes: &[u8], variant: Base64 ) -> String {
+
+	let alphabet = if variant.url_safe( ) { URL_SAFE_ALPHABET } else { STANDARD_ALPHABET };
+	let mut allo = String::with_capacity( bytes.len( ).div_ceil( 3 ) * 4 );
+
+	for chunk in bytes.chunks( 3 ) {
+		let group = chunk.iter( ).enumerate( ).fold( 0u32, |group, ( i, &byte )| group | u32::from( byte ) << ( 16 - 8 * i ) );
+		let symbols = chunk.len( ) + 1;
+
+		for i in 0..4 {
+			if i < symbols {
+				allo.push( alphabet[ ( group >> ( 18 - 6 * i ) & 0x3F ) as usize ] as char );
+			} else if variant.padded( ) {
+				allo.push( '=' );
+			}
+		}
+	}
+
+	if variant == Base64::Mime && allo.len( ) > 76 {
+		let lines: Vec<&str> = allo.as_bytes( ).chunks( 76 ).map( |line| ::alloc::str::from_utf8( line ).unwrap_or( "" ) ).collect( );
+		return lines.join( "\r\n" );
+	}
+
+	allo
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_decode_base64_bytes( ) {
+
+		// RFC 4648 §10 test vectors
+		for &( plain, encoded ) in &[ ( "", "" ), ( "f", "Zg==" ), ( "fo", "Zm8=" ), ( "foo", "Zm9v" ),
+				( "foob", "Zm9vYg==" ), ( "fooba", "Zm9vYmE=" ), ( "foobar", "Zm9vYmFy" ) ] {
+			assert_eq!( decode_base64_bytes( encoded.as_bytes( ), Base64::Standard ), Ok( plain.as_bytes( ).to_vec( ) ) );
+			assert_eq!( decode_base64_bytes( encoded.as_bytes( ), Base64::Mime ), Ok( plain.as_bytes( ).to_vec( ) ) );
+			assert_eq!( decode_base64_bytes( encoded.trim_end_matches( '=' ).as_bytes( ), Base64::Unpadded ), Ok( plain.as_bytes( ).to_vec( ) ) );
+		}
+
+		assert_eq!( decode_base64_bytes( b"+/+/", Base64::Standard ),		Ok( vec![ 0xFB, 0xFF, 0xBF ] ) );
+		assert_eq!( decode_base64_bytes( b"-_-_", Base64::UrlSafe ),		Ok( vec![ 0xFB, 0xFF, 0xBF ] ) );
+		assert_eq!( decode_base64_bytes( b"Zm9v\r\nYmFy\r\n", Base64::Mime ),	Ok( b"foobar".to_vec( ) ) );
+		assert_eq!( decode_base64_bytes( b"Zm8", Base64::Mime ),			Ok( b"fo".to_vec( ) ) );
+	}
+
+	#[test]
+	fn test_decode_base64_bytes_failure( ) {
+
+		assert_eq!( decode_base64_bytes( b"Zm9v\nYmFy", Base64::Standard ),	Err( DecodeError::InvalidByte { offset: 4, byte: b'\n' } ) );
+		assert_eq!( decode_base64_bytes( b"-_-_", Base64::Standard ),		Err( DecodeError::InvalidByte { offset: 0, byte: b'-' } ) );
+		assert_eq!( decode_base64_bytes( b"+/+/", Base64::UrlSafe ),		Err( DecodeError::InvalidByte { offset: 0, byte: b'+' } ) );
+		assert_eq!( decode_base64_bytes( "¢".as_bytes( ), Base64::Mime ),	Err( DecodeError::InvalidByte { offset: 0, byte: 0xC2 } ) );
+		assert_eq!( decode_base64_bytes( b"Zm9vY", Base64::Unpadded ),		Err( DecodeError::InvalidLength { length: 5 } ) );
+		assert_eq!( decode_base64_bytes( b"Zm8", Base64::Standard ),		Err( DecodeError::InvalidPadding { offset: 3 } ) );
+		assert_eq!( decode_base64_bytes( b"Zm8==", Base64::Standard ),		Err( DecodeError::InvalidPadding { offset: 3 } ) );
+		assert_eq!( decode_base64_bytes( b"Zg==Zg==", Base64::Standard ),	Err( DecodeError::InvalidPadding { offset: 2 } ) );
+		assert_eq!( decode_base64_bytes( b"Zm8=", Base64::Unpadded ),		Err( DecodeError::InvalidPadding { offset: 3 } ) );
+		assert_eq!( decode_base64_bytes( b"Zh==", Base64::Standard ),		Err( DecodeError::TrailingBits { offset: 1 } ) );
+		assert_eq!( decode_base64_bytes( b"Zh==", Base64::Mime ),			Ok( b"f".to_vec( ) ) );
+	}
+
+	#[test]
+	#[cfg( feature = "std" )]
+	fn test_decode_base64( ) {
+
+		assert_eq!( decode_base64( "dGVzdA==", Base64::Standard, "utf-8" ),	Ok( "test".to_string( ) ) );
+		assert_eq!( decode_base64( "7eXs+SDv4SDp7Oj08A==", Base64::Standard, "iso-8859-8" ),	Ok( "םולש ןב ילטפנ".to_string( ) ) );
+		assert_eq!( decode_base64( "GyRCIVpIL0NtPXE8dT8uIVsbKEI=", Base64::Standard, "iso-2022-jp" ),	Ok( "【発注書受信】".to_string( ) ) );
+		assert_eq!( decode_base64( "6Q", Base64::Unpadded, "latin1" ),		Ok( "é".to_string( ) ) );
+		assert!( decode_base64( "6Q", Base64::Standard, "latin1" ).is_err( ) );
+	}
+
+	#[test]
+	fn test_encode_base64( ) {
+
+		assert_eq!( encode_base64( b"", Base64::Standard ),				"" );
+		assert_eq!( encode_base64( b"foob", Base64::Standard ),			"Zm9vYg==" );
+		assert_eq!( encode_base64( b"foob", Base64::Unpadded ),			"Zm9vYg" );
+		assert_eq!( encode_base64( &[ 0xFB, 0xFF ], Base64::UrlSafe ),		"-_8=" );
+		assert_eq!( encode_base64( &[ 0xFB, 0xFF ], Base64::UrlSafeUnpadded ),	"-_8" );
+
+		let long = [ 0u8; 60 ];
+		let mime = encode_base64( &long, Base64::Mime );
+		assert_eq!( mime.lines( ).map( |line| line.trim_end( ).len( ) ).collect::<Vec<_>>( ), vec![ 76, 4 ] );
+		assert_eq!( decode_base64_bytes( mime.as_bytes( ), Base64::Mime ), Ok( long.to_vec( ) ) );
+
+		for variant in &[ Base64::Standard, Base64::UrlSafe, Base64::Mime, Base64::Unpadded, Base64::UrlSafeUnpadded ] {
+			for length in 0..8 {
+				let bytes: Vec<u8> = ( 0..length ).map( |i| ( i * 77 + 250 ) as u8 ).collect( );
+				assert_eq!( decode_base64_bytes( encode_base64( &bytes, *variant ).as_bytes( ), *variant ), Ok( bytes ) );
+			}
+		}
+	}
+}
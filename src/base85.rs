@@ -0,0 +1,216 @@
+// Base85, in the Adobe Ascii85 of PostScript and PDF and in ZeroMQ’s Z85
+// See: PostScript Language Reference, ASCII85Decode filter
+// See also: ZeroMQ RFC 32/Z85 https://rfc.zeromq.org/spec/32/
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg( feature = "std" )]
+use charset::decode_charset;
+use error::DecodeError;
+
+static Z85_ALPHABET: &[u8; 85] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// Which base85 to speak
+#[derive( Clone, Copy, Debug, PartialEq, Eq )]
+pub enum Base85 {
+	/// Adobe Ascii85: `!` to `u`, `z` for four zero bytes, optional `<~ ~>` delimiters, whitespace ignored
+	Ascii85,
+	/// ZeroMQ Z85: a quote and backslash free alphabet, whole groups of four bytes only
+	Z85,
+}
+
+/// The value a base85 symbol stands for
+fn digit( byte: u8, variant: Base85 ) -> Option<u32> {
+	match variant {
+		Base85::Ascii85 => match byte {
+			b'!' ..= b'u' => Some( u32::from( byte - b'!' ) ),
+			_ => None
+		},
+		Base85::Z85 => Z85_ALPHABET.iter( ).position( |&symbol| symbol == byte ).map( |value| value as u32 ),
+	}
+}
+
+/// Decodes base85 into the bytes it carries
+///
+/// # Examples
+///
+/// ```
+/// use mung::{decode_base85_bytes, Base85};
+/// assert_eq!( decode_base85_bytes( b"<~9jqo^~>", Base85::Ascii85 ), Ok( b"Man ".to_vec( ) ) );
+/// assert_eq!( decode_base85_bytes( b"HelloWorld", Base85::Z85 ), Ok( vec![ 0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B ] ) );
+/// ```
+pub fn decode_base85_bytes( s: &[u8], variant: Base85 ) -> Result<Vec<u8>, DecodeError> {
+
+	let mut s = s;
+	let mut start = 0;
+	if variant == Base85::Ascii85 {
+		// PDF streams carry only the closing delimiter
+		if s.starts_with( b"<~" ) {
+			s = &s[ 2..];
+			start = 2;
+		}
+		if s.ends_with( b"~>" ) {
+			s = &s[ ..s.len( ) - 2 ];
+		}
+	}
+
+	let mut allo = Vec::with_capacity( s.len( ) * 4 / 5 );
+	let mut group = 0u64;
+	let mut symbols = 0usize;
+	let mut group_at = start;
+
+	for ( offset, &byte ) in s.iter( ).enumerate( ).map( |( i, byte )| ( i + start, byte ) ) {
+
+		if variant == Base85::Ascii85 {
+			if byte == b' ' || byte == b'\t' || byte == b'\r' || byte == b'\n' || byte == b'\x0C' || byte == 0 {
+				continue;
+			}
+			if byte == b'z' {
+				if symbols != 0 {
+					return Err( DecodeError::InvalidByte { offset, byte } );
+				}
+				allo.extend_from_slice( &[ 0, 0, 0, 0 ] );
+				continue;
+			}
+		}
+
+		let value = match digit( byte, variant ) {
+			Some( value ) => value,
+			None => return Err( DecodeError::InvalidByte { offset, byte } ),
+		};
+		if symbols == 0 {
+			group_at = offset;
+		}
+		group = group * 85 + u64::from( value );
+		symbols += 1;
+
+		if symbols == 5 {
+			if group > u64::from( u32::MAX ) {
+				return Err( DecodeError::Overflow { offset: group_at } );
+			}
+			allo.extend_from_slice( &[ ( group >> 24 ) as u8, ( group >> 16 ) as u8, ( group >> 8 ) as u8, group as u8 ] );
+			group = 0;
+			symbols = 0;
+		}
+	}
+
+	if symbols != 0 {
+		// Ascii85 ends a short group by padding it out with the highest digit, u
+		if variant == Base85::Z85 || symbols == 1 {
+			return Err( DecodeError::InvalidLength { length: s.len( ) } );
+		}
+		for _ in symbols..5 {
+			group = group * 85 + 84;
+		}
+		if group > u64::from( u32::MAX ) {
+			return Err( DecodeError::Overflow { offset: group_at } );
+		}
+		let bytes = [ ( group >> 24 ) as u8, ( group >> 16 ) as u8, ( group >> 8 ) as u8, group as u8 ];
+		allo.extend_from_slice( &bytes[ ..symbols - 1 ] );
+	}
+
+	Ok( allo )
+}
+
+/// Decodes base85 into text, reading the carried bytes in the named charset
+#[cfg( feature = "std" )]
+pub fn decode_base85( s: &str, variant: Base85, charset: &str ) -> Result<String, DecodeError> {
+	decode_base85_bytes( s.as_bytes( ), variant ).map( |bytes| decode_charset( &bytes, charset ).into_owned( ) )
+}
+
+/// Encodes bytes as base85, without Ascii85’s `<~ ~>` delimiters
+///
+/// Z85 only defines whole groups, so `None` is returned when `bytes` is not
+/// a multiple of four long.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{encode_base85, Base85};
+/// assert_eq!( encode_base85( b"Man ", Base85::Ascii85 ), Some( "9jqo^".to_string( ) ) );
+/// assert_eq!( encode_base85( b"Man", Base85::Z85 ), None );
+/// ```
+pub fn encode_base85( bytes: &[u8], variant: Base85 ) -> Option<String> {
+
+	if variant == Base85::Z85 && !bytes.len( ).is_multiple_of( 4 ) {
+		return None;
+	}
+
+	let mut allo = String::with_capacity( bytes.len( ).div_ceil( 4 ) * 5 );
+
+	for chunk in bytes.chunks( 4 ) {
+		let group = chunk.iter( ).enumerate( ).fold( 0u32, |group, ( i, &byte )| group | u32::from( byte ) << ( 24 - 8 * i ) );
+
+		if variant == Base85::Ascii85 && group == 0 && chunk.len( ) == 4 {
+			allo.push( 'z' );
+			continue;
+		}
+
+		let mut digits = [ 0u8; 5 ];
+		let mut rest = group;
+		for place in digits.iter_mut( ).rev( ) {
+			*place = ( rest % 85 ) as u8;
+			rest /= 85;
+		}
+		for &value in &digits[ ..chunk.len( ) + 1 ] {
+			allo.push( match variant {
+				Base85::Ascii85 => ( value + b'!' ) as char,
+				Base85::Z85 => Z85_ALPHABET[ value as usize ] as char,
+			} );
+		}
+	}
+
+	Some( allo )
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_ascii85( ) {
+
+		assert_eq!( decode_base85_bytes( b"", Base85::Ascii85 ),					Ok( vec![ ] ) );
+		assert_eq!( decode_base85_bytes( b"9jqo^BlbD-BleB1DJ+*+F(f,q", Base85::Ascii85 ),	Ok( b"Man is distinguished".to_vec( ) ) );
+		assert_eq!( decode_base85_bytes( b"<~9jqo^\r\nBlbD-~>", Base85::Ascii85 ),	Ok( b"Man is d".to_vec( ) ) );
+		assert_eq!( decode_base85_bytes( b"9jqo^~>", Base85::Ascii85 ),			Ok( b"Man ".to_vec( ) ) );
+		assert_eq!( decode_base85_bytes( b"zz", Base85::Ascii85 ),				Ok( vec![ 0; 8 ] ) );
+		assert_eq!( decode_base85_bytes( b"/c", Base85::Ascii85 ),				Ok( b".".to_vec( ) ) );
+		#[cfg( feature = "std" )]
+		assert_eq!( decode_base85( "<~6#L4Rkl~>", Base85::Ascii85, "latin1" ),		Ok( "André".to_string( ) ) );
+
+		assert_eq!( encode_base85( b"Man is distinguished", Base85::Ascii85 ),	Some( "9jqo^BlbD-BleB1DJ+*+F(f,q".to_string( ) ) );
+		assert_eq!( encode_base85( &[ 0; 5 ], Base85::Ascii85 ),				Some( "z!!".to_string( ) ) );
+		assert_eq!( encode_base85( b".", Base85::Ascii85 ),						Some( "/c".to_string( ) ) );
+		for length in 0..9 {
+			let bytes: Vec<u8> = ( 0..length ).map( |i| ( i * 97 + 200 ) as u8 ).collect( );
+			assert_eq!( decode_base85_bytes( encode_base85( &bytes, Base85::Ascii85 ).unwrap( ).as_bytes( ), Base85::Ascii85 ), Ok( bytes ) );
+		}
+	}
+
+	#[test]
+	fn test_ascii85_failure( ) {
+
+		assert_eq!( decode_base85_bytes( b"9jqo^v", Base85::Ascii85 ),			Err( DecodeError::InvalidByte { offset: 5, byte: b'v' } ) );
+		assert_eq!( decode_base85_bytes( b"9jqzo^", Base85::Ascii85 ),			Err( DecodeError::InvalidByte { offset: 3, byte: b'z' } ) );
+		assert_eq!( decode_base85_bytes( b"<~9jqo^B~>", Base85::Ascii85 ),		Err( DecodeError::InvalidLength { length: 6 } ) );
+		assert_eq!( decode_base85_bytes( b"9jqo^uuuuu", Base85::Ascii85 ),		Err( DecodeError::Overflow { offset: 5 } ) );
+	}
+
+	#[test]
+	fn test_z85( ) {
+
+		// From the Z85 specification
+		let hello = [ 0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B ];
+		assert_eq!( decode_base85_bytes( b"HelloWorld", Base85::Z85 ),			Ok( hello.to_vec( ) ) );
+		assert_eq!( encode_base85( &hello, Base85::Z85 ),						Some( "HelloWorld".to_string( ) ) );
+		assert_eq!( encode_base85( b"abc", Base85::Z85 ),						None );
+
+		assert_eq!( decode_base85_bytes( b"Hello", Base85::Z85 ),				Ok( hello[ ..4 ].to_vec( ) ) );
+		assert_eq!( decode_base85_bytes( b"Hell", Base85::Z85 ),				Err( DecodeError::InvalidLength { length: 4 } ) );
+		assert_eq!( decode_base85_bytes( b"Hello World", Base85::Z85 ),			Err( DecodeError::InvalidByte { offset: 5, byte: b' ' } ) );
+		assert_eq!( decode_base85_bytes( b"%%%%%", Base85::Z85 ),				Err( DecodeError::Overflow { offset: 0 } ) );
+	}
+}
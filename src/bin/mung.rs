@@ -0,0 +1,460 @@
+//! The `mung` command, for undoing and applying escapements in shells and pipelines
+//!
+//! ```text
+//! mung decode entities page.html
+//! echo 'a b&c' | mung encode url --set query
+//! mung decode rfc2047 --lines headers.txt
+//! mung auto --explain < mystery.txt
+//! ```
+
+extern crate mung;
+
+use std::env;
+use std::fs;
+use std::mem;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::process;
+
+use mung::{decode_charset, entity_tokens, percent_tokens, quoted_printable_tokens, rfc2047_tokens};
+use mung::{Decode, Encode, EncodeSet, EntitySet, Pipeline, Registry, Token};
+
+const USAGE: &str = "usage: mung decode <codecs> [options] [file ...]
+       mung encode <codecs> [options] [file ...]
+       mung auto [options] [file ...]
+
+Codecs are named as the Registry knows them, such as entities, html5, url,
+rfc2047, quoted-printable, json, base64 or idna; give several as url,html
+to apply them in turn. auto decodes whatever escapes it recognises, layer
+upon layer. Files default to stdin, as does -; a line break at the end of
+the input, or of a line, is passed through as it is.
+
+options:
+    --set <set>          for entities: xml, html4, xhtml or html5
+                         for encoding url: query, path or url
+    --charset <label>    read the input in this charset rather than UTF-8
+    --lines              treat each line as its own input
+    --explain            tell stderr what each layer did
+    --help               show this message";
+
+/// How deep `auto` digs before it gives up on text that keeps decoding
+const AUTO_LAYERS: usize = 8;
+
+/// What to do with the input
+#[derive( Clone, Debug, PartialEq, Eq )]
+enum Command {
+	Decode( String ),
+	Encode( String ),
+	Auto,
+	Help,
+}
+
+/// The command line, read
+#[derive( Clone, Debug, PartialEq, Eq )]
+struct Options {
+	command: Command,
+	set: Option<String>,
+	charset: Option<String>,
+	lines: bool,
+	explain: bool,
+	files: Vec<String>,
+}
+
+/// What one codec made of the text it was given
+struct Layer {
+	name: String,
+	changed: bool,
+	text: String,
+}
+
+/// Why the command stopped early
+#[derive( Debug, PartialEq, Eq )]
+enum Failure {
+	/// Whatever read stdout went away, as head does once it has its lines
+	Closed,
+	Message( String ),
+}
+
+impl From<String> for Failure {
+	fn from( message: String ) -> Failure {
+		Failure::Message( message )
+	}
+}
+
+/// The codecs to run, looked up once
+enum Plan<'r> {
+	Decode( Pipeline<'r, dyn Decode + 'r> ),
+	Encode( Pipeline<'r, dyn Encode + 'r> ),
+	Auto,
+}
+
+fn main( ) {
+	let options = match parse_args( env::args( ).skip( 1 ) ) {
+		Ok( options ) => options,
+		Err( message ) => {
+			eprintln!( "mung: {}\n\n{}", message, USAGE );
+			process::exit( 2 );
+		},
+	};
+	if options.command == Command::Help {
+		// Nothing is lost if stdout has gone away, as when piped into head
+		let _ = writeln!( io::stdout( ), "{}", USAGE );
+		return;
+	}
+	match run( &options ) {
+		Ok( ( ) ) | Err( Failure::Closed ) => { },
+		Err( Failure::Message( message ) ) => {
+			eprintln!( "mung: {}", message );
+			process::exit( 1 );
+		},
+	}
+}
+
+/// Reads the arguments that follow the program name
+fn parse_args<I: Iterator<Item = String>>( mut args: I ) -> Result<Options, String> {
+
+	let command = match args.next( ).as_deref( ) {
+		Some( "decode" ) => codecs( args.next( ), "decode" )?.map_or( Command::Help, Command::Decode ),
+		Some( "encode" ) => codecs( args.next( ), "encode" )?.map_or( Command::Help, Command::Encode ),
+		Some( "auto" ) => Command::Auto,
+		Some( "help" ) | Some( "-h" ) | Some( "--help" ) => Command::Help,
+		Some( other ) => return Err( format!( "unknown command {:?}", other ) ),
+		None => return Err( "no command given".into( ) ),
+	};
+
+	let mut options = Options { command, set: None, charset: None, lines: false, explain: false, files: Vec::new( ) };
+	let mut files_only = false;
+	while let Some( arg ) = args.next( ) {
+		if files_only || arg == "-" || !arg.starts_with( '-' ) {
+			options.files.push( arg );
+			continue;
+		}
+		match arg.as_str( ) {
+			"--set" => options.set = Some( args.next( ).ok_or( "--set needs a value" )? ),
+			"--charset" => options.charset = Some( args.next( ).ok_or( "--charset needs a value" )? ),
+			"--lines" => options.lines = true,
+			"--explain" => options.explain = true,
+			"-h" | "--help" => options.command = Command::Help,
+			"--" => files_only = true,
+			_ => return Err( format!( "unknown option {:?}", arg ) ),
+		}
+	}
+	Ok( options )
+}
+
+/// The codecs given to `command`, or none when help was asked for instead
+fn codecs( arg: Option<String>, command: &str ) -> Result<Option<String>, String> {
+	match arg {
+		Some( ref arg ) if arg == "-h" || arg == "--help" => Ok( None ),
+		Some( ref arg ) if arg.starts_with( '-' ) => Err( format!( "{} needs its codecs before {}", command, arg ) ),
+		Some( arg ) => Ok( Some( arg ) ),
+		None => Err( format!( "{} needs a codec", command ) ),
+	}
+}
+
+fn run( options: &Options ) -> Result<( ), Failure> {
+
+	let mut registry = Registry::new( );
+	if let Some( ref set ) = options.set {
+		apply_set( &mut registry, &options.command, set )?;
+	}
+	let plan = match options.command {
+		Command::Decode( ref spec ) => Plan::Decode( registry.parse_decoders( spec ).map_err( |error| error.to_string( ) )? ),
+		Command::Encode( ref spec ) => Plan::Encode( registry.parse_encoders( spec ).map_err( |error| error.to_string( ) )? ),
+		_ => Plan::Auto,
+	};
+
+	let stdin = vec![ "-".to_string( ) ];
+	let files = if options.files.is_empty( ) { &stdin } else { &options.files };
+	let stdout = io::stdout( );
+	let mut out = stdout.lock( );
+	let mut line = 0;
+
+	for file in files {
+		if options.lines {
+			// Each line goes out as soon as it comes in, so that tail -f can feed us
+			let mut reader = open( file )?;
+			let mut bytes = Vec::new( );
+			loop {
+				if reader.read_until( b'\n', &mut bytes ).map_err( |error| format!( "{}: {}", file, error ) )? == 0 {
+					break;
+				}
+				line += 1;
+				let piece = text( mem::take( &mut bytes ), file, options.charset.as_deref( ) )?;
+				let body = without_line_break( &piece );
+				let layers = apply( &plan, &registry, body ).map_err( |message| format!( "line {}: {}", line, message ) )?;
+				if options.explain {
+					explain( &layers, Some( line ) );
+				}
+				write( &mut out, output( body, &layers ) )?;
+				write( &mut out, &piece[ body.len( )..] )?;
+				out.flush( ).map_err( closed )?;
+			}
+			continue;
+		}
+
+		let mut bytes = Vec::new( );
+		open( file )?.read_to_end( &mut bytes ).map_err( |error| format!( "{}: {}", file, error ) )?;
+		let text = text( bytes, file, options.charset.as_deref( ) )?;
+		let body = without_line_break( &text );
+		let layers = apply( &plan, &registry, body )?;
+		if options.explain {
+			explain( &layers, None );
+		}
+		write( &mut out, output( body, &layers ) )?;
+		write( &mut out, &text[ body.len( )..] )?;
+	}
+	out.flush( ).map_err( closed )
+}
+
+/// Takes each codec of a `--set` command that the set applies to over with the variant it names
+fn apply_set( registry: &mut Registry, command: &Command, set: &str ) -> Result<( ), String> {
+
+	let ( spec, encoding ) = match *command {
+		Command::Decode( ref spec ) => ( spec, false ),
+		Command::Encode( ref spec ) => ( spec, true ),
+		_ => return Err( "--set applies only to decode and encode".into( ) ),
+	};
+	let entities = match set.to_ascii_lowercase( ).as_str( ) {
+		"xml" => Some( EntitySet::Xml ),
+		"html4" => Some( EntitySet::Html4 ),
+		"xhtml" | "xhtml1" => Some( EntitySet::Xhtml1 ),
+		"html5" => Some( EntitySet::Html5 ),
+		_ => None,
+	};
+	let percent = match set.to_ascii_lowercase( ).as_str( ) {
+		"query" | "component" => Some( EncodeSet::Component ),
+		"path" => Some( EncodeSet::Path ),
+		"url" | "full" => Some( EncodeSet::Url ),
+		_ => None,
+	};
+
+	let mut applied = false;
+	let names = spec.trim( ).trim_start_matches( '[' ).trim_end_matches( ']' ).split( &[ ',', '|' ][ ..] );
+	for name in names.map( |name| name.trim( ).to_ascii_lowercase( ).replace( '_', "-" ) ) {
+		match ( name.as_str( ), entities, percent ) {
+			( "entities", Some( entities ), _ ) | ( "html", Some( entities ), _ ) | ( "xhtml", Some( entities ), _ )
+			| ( "html4", Some( entities ), _ ) | ( "html5", Some( entities ), _ ) | ( "xml-entities", Some( entities ), _ ) => if encoding {
+				registry.register_encoder( &[ &name ], entities );
+			} else {
+				registry.register_decoder( &[ &name ], entities );
+			},
+			( "url", _, Some( percent ) ) | ( "percent", _, Some( percent ) ) | ( "rfc1738", _, Some( percent ) ) | ( "uri", _, Some( percent ) )
+			| ( "url-path", _, Some( percent ) ) | ( "url-full", _, Some( percent ) ) if encoding => registry.register_encoder( &[ &name ], percent ),
+			_ => continue,
+		}
+		applied = true;
+	}
+	if !applied {
+		return Err( format!( "--set {} does not apply to {} {}", set, if encoding { "encode" } else { "decode" }, spec ) );
+	}
+	Ok( ( ) )
+}
+
+/// A file, or stdin for `-`, to be read
+fn open( file: &str ) -> Result<Box<dyn BufRead>, String> {
+	if file == "-" {
+		return Ok( Box::new( BufReader::new( io::stdin( ) ) ) );
+	}
+	match fs::File::open( file ) {
+		Ok( f ) => Ok( Box::new( BufReader::new( f ) ) ),
+		Err( error ) => Err( format!( "{}: {}", file, error ) ),
+	}
+}
+
+/// Bytes read from `file` as text, in `charset` or else UTF-8
+fn text( bytes: Vec<u8>, file: &str, charset: Option<&str> ) -> Result<String, String> {
+	match charset {
+		Some( charset ) => Ok( decode_charset( &bytes, charset ).into_owned( ) ),
+		None => String::from_utf8( bytes ).map_err( |_| format!( "{}: not UTF-8; name its charset with --charset", file ) ),
+	}
+}
+
+/// `text` but for the line break it ends with, which is passed through as it is
+fn without_line_break( text: &str ) -> &str {
+	let text = text.strip_suffix( '\n' ).unwrap_or( text );
+	text.strip_suffix( '\r' ).unwrap_or( text )
+}
+
+fn write<W: Write>( out: &mut W, text: &str ) -> Result<( ), Failure> {
+	out.write_all( text.as_bytes( ) ).map_err( closed )
+}
+
+/// A broken pipe only means that nobody wants more output
+fn closed( error: io::Error ) -> Failure {
+	if error.kind( ) == ErrorKind::BrokenPipe {
+		Failure::Closed
+	} else {
+		Failure::Message( error.to_string( ) )
+	}
+}
+
+/// Runs `text` through the plan, one layer at a time
+fn apply( plan: &Plan, registry: &Registry, text: &str ) -> Result<Vec<Layer>, String> {
+	let mut layers: Vec<Layer> = Vec::new( );
+	match *plan {
+		Plan::Decode( ref pipeline ) => for &( name, stage ) in pipeline.stages( ) {
+			let decoded = stage.decode( output( text, &layers ) ).map_err( |error| format!( "{}: {}", name, error ) )?.into_owned( );
+			let changed = decoded != output( text, &layers );
+			layers.push( Layer { name: name.to_string( ), changed, text: decoded } );
+		},
+		Plan::Encode( ref pipeline ) => for &( name, stage ) in pipeline.stages( ) {
+			let encoded = stage.encode( output( text, &layers ) ).into_owned( );
+			let changed = encoded != output( text, &layers );
+			layers.push( Layer { name: name.to_string( ), changed, text: encoded } );
+		},
+		Plan::Auto => layers = auto( registry, text ),
+	}
+	Ok( layers )
+}
+
+/// The text after the last layer
+fn output<'a>( text: &'a str, layers: &'a [Layer] ) -> &'a str {
+	layers.last( ).map_or( text, |layer| &layer.text )
+}
+
+/// Whether text holds an escape of some escapement
+type Finder = fn( &str ) -> bool;
+
+/// The decoders `auto` tries, in order, each with a test for its escapes
+static AUTO: &[( &str, Finder )] = &[
+	( "rfc2047", has_encoded_word ),
+	( "url", has_percent_triplet ),
+	( "html5", has_entity ),
+	( "quoted-printable", has_quoted_byte ),
+];
+
+fn has_encoded_word( s: &str ) -> bool {
+	rfc2047_tokens( s ).any( |token| matches!( token, Token::EncodedWord { .. } ) )
+}
+
+fn has_percent_triplet( s: &str ) -> bool {
+	percent_tokens( s ).any( |token| matches!( token, Token::PercentTriplet { .. } ) )
+}
+
+fn has_entity( s: &str ) -> bool {
+	entity_tokens( s, EntitySet::Html5 ).any( |token| matches!( token, Token::NamedEntity { .. } | Token::NumericRef { .. } ) )
+}
+
+fn has_quoted_byte( s: &str ) -> bool {
+	quoted_printable_tokens( s ).any( |token| matches!( token, Token::QuotedByte { .. } | Token::SoftLineBreak { .. } ) )
+}
+
+/// Peels off whichever escapement `AUTO` first finds, again and again, until none is left
+fn auto( registry: &Registry, text: &str ) -> Vec<Layer> {
+	let mut layers: Vec<Layer> = Vec::new( );
+	'layers: while layers.len( ) < AUTO_LAYERS {
+		for &( name, found ) in AUTO {
+			let current = output( text, &layers );
+			if !found( current ) {
+				continue;
+			}
+			let decoded = match registry.decoder( name ).map( |decoder| decoder.decode( current ) ) {
+				Some( Ok( decoded ) ) => decoded.into_owned( ),
+				_ => continue,
+			};
+			if decoded != current {
+				layers.push( Layer { name: name.to_string( ), changed: true, text: decoded } );
+				continue 'layers;
+			}
+		}
+		break;
+	}
+	layers
+}
+
+/// Tells stderr what each layer did, and on which line when reading line by line
+fn explain( layers: &[Layer], line: Option<usize> ) {
+	let prefix = line.map_or( String::new( ), |line| format!( "line {}: ", line ) );
+	if layers.is_empty( ) {
+		eprintln!( "{}nothing recognised", prefix );
+	}
+	for layer in layers {
+		if layer.changed {
+			eprintln!( "{}{}: {:?}", prefix, layer.name, layer.text );
+		} else {
+			eprintln!( "{}{}: unchanged", prefix, layer.name );
+		}
+	}
+}
+
+
+#[cfg( test )]
+mod tests {
+
+	use super::*;
+
+	fn args( line: &str ) -> Result<Options, String> {
+		parse_args( line.split_whitespace( ).map( String::from ) )
+	}
+
+	fn names( layers: &[Layer] ) -> Vec<&str> {
+		layers.iter( ).map( |layer| layer.name.as_str( ) ).collect( )
+	}
+
+	#[test]
+	fn test_parse_args( ) {
+		let options = args( "encode url --set query --explain a.txt - --lines" ).unwrap( );
+		assert_eq!( options.command,	Command::Encode( "url".into( ) ) );
+		assert_eq!( options.set,		Some( "query".into( ) ) );
+		assert_eq!( options.files,		vec![ "a.txt".to_string( ), "-".to_string( ) ] );
+		assert!( options.explain && options.lines );
+		assert_eq!( args( "decode rfc2047 --charset latin1 -- --lines" ).unwrap( ).files,	vec![ "--lines".to_string( ) ] );
+		assert_eq!( args( "auto --help" ).unwrap( ).command,	Command::Help );
+		assert_eq!( args( "decode --help" ).unwrap( ).command,	Command::Help );
+		assert_eq!( args( "encode -h" ).unwrap( ).command,	Command::Help );
+		assert!( args( "decode --lines url" ).is_err( ) );
+		assert!( args( "" ).is_err( ) );
+		assert!( args( "decode" ).is_err( ) );
+		assert!( args( "frobnicate" ).is_err( ) );
+		assert!( args( "auto --loud" ).is_err( ) );
+		assert!( args( "auto --set" ).is_err( ) );
+	}
+
+	#[test]
+	fn test_closed( ) {
+		assert_eq!( closed( io::Error::from( ErrorKind::BrokenPipe ) ),	Failure::Closed );
+		assert!( closed( io::Error::from( ErrorKind::PermissionDenied ) ) != Failure::Closed );
+	}
+
+	#[test]
+	fn test_without_line_break( ) {
+		assert_eq!( without_line_break( "a b\r\n" ),	"a b" );
+		assert_eq!( without_line_break( "a b\n\n" ),	"a b\n" );
+		assert_eq!( without_line_break( "a b" ),		"a b" );
+	}
+
+	#[test]
+	fn test_plans( ) {
+		let mut registry = Registry::new( );
+		let command = Command::Encode( "url".into( ) );
+		apply_set( &mut registry, &command, "path" ).unwrap( );
+		let plan = Plan::Encode( registry.parse_encoders( "url" ).unwrap( ) );
+		assert_eq!( output( "a b/c", &apply( &plan, &registry, "a b/c" ).unwrap( ) ),	"a%20b/c" );
+
+		let plan = Plan::Decode( registry.parse_decoders( "url, entities" ).unwrap( ) );
+		let layers = apply( &plan, &registry, "Fish%20&amp;%20Chips" ).unwrap( );
+		assert_eq!( names( &layers ),	vec![ "url", "html" ] );
+		assert_eq!( output( "", &layers ),	"Fish & Chips" );
+		assert!( apply( &Plan::Decode( registry.parse_decoders( "base64" ).unwrap( ) ), &registry, "!!" ).is_err( ) );
+
+		assert!( apply_set( &mut registry, &Command::Decode( "url".into( ) ), "query" ).is_err( ) );
+		assert!( apply_set( &mut registry, &Command::Decode( "html".into( ) ), "query" ).is_err( ) );
+		assert!( apply_set( &mut registry, &Command::Auto, "xml" ).is_err( ) );
+		apply_set( &mut registry, &Command::Decode( "url, entities".into( ) ), "xml" ).unwrap( );
+		let plan = Plan::Decode( registry.parse_decoders( "url, entities" ).unwrap( ) );
+		assert_eq!( output( "", &apply( &plan, &registry, "%26lt;&nbsp;" ).unwrap( ) ),	"<&nbsp;" );
+		apply_set( &mut registry, &Command::Decode( "entities".into( ) ), "xml" ).unwrap( );
+		let plan = Plan::Decode( registry.parse_decoders( "entities" ).unwrap( ) );
+		assert_eq!( output( "", &apply( &plan, &registry, "&lt;&nbsp;" ).unwrap( ) ),	"<&nbsp;" );
+	}
+
+	#[test]
+	fn test_auto( ) {
+		let registry = Registry::new( );
+		let layers = auto( &registry, "=?UTF-8?Q?Fish_%26amp%3B_Chips?=" );
+		assert_eq!( names( &layers ),	vec![ "rfc2047", "url", "html5" ] );
+		assert_eq!( output( "", &layers ),	"Fish & Chips" );
+		assert_eq!( output( "", &auto( &registry, "caf=C3=A9=\n" ) ),	"café" );
+		assert!( auto( &registry, "plain text, 100% & a=b" ).is_empty( ) );
+	}
+}
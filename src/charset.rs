@@ -0,0 +1,61 @@
+// The charset layer: turning bytes that came out of an escapement back into text
+
+use std::borrow::Cow;
+
+use encoding::{DecoderTrap, EncodingRef};
+use encoding::label::encoding_from_whatwg_label;
+
+use utf7::decode_utf7_bytes;
+
+/// Finds the decoder for a charset label, falling back to Latin-1 for those we do not know
+// XXX Spec suggests we pass back original message, or a warning if things like Charset are unknown
+fn lookup( charset: &str ) -> EncodingRef {
+	encoding_from_whatwg_label( charset ).unwrap_or( encoding::all::ISO_8859_1 )
+}
+
+/// Decodes bytes in the named charset into their UTF-8 form
+///
+/// Labels are matched as WHATWG does, so `latin1`, `ISO-8859-1` and
+/// `iso_8859-1` all name the same charset. Unknown labels are read as
+/// ISO-8859-1, so no byte is ever lost, and malformed sequences become �.
+/// UTF-7, which WHATWG deliberately leaves out, is understood too.
+///
+/// # Examples
+///
+/// ```
+/// use mung::decode_charset;
+/// assert_eq!( decode_charset( b"Andr\xE9", "ISO-8859-1" ), "André" );
+/// ```
+pub fn decode_charset<'a>( bytes: &'a [u8], charset: &str ) -> Cow<'a, str> {
+
+	// XXX this branch could be removed to use encoding instead? is it slower?
+	if charset.eq_ignore_ascii_case( "utf-8" ) {
+		return String::from_utf8_lossy( bytes );
+	}
+	if charset.eq_ignore_ascii_case( "utf-7" ) || charset.eq_ignore_ascii_case( "unicode-1-1-utf-7" ) {
+		return decode_utf7_bytes( bytes, false ).into( );
+	}
+
+	// TODO Make � replacement an option
+	lookup( charset ).decode( bytes, DecoderTrap::Replace ).unwrap_or_else( |_| "�".to_string( ) ).into( )
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_decode_charset( ) {
+
+		assert_eq!( decode_charset( b"", "utf-8" ),						"" );
+		assert_eq!( decode_charset( b"caf\xC3\xA9", "utf-8" ),			"café" );
+		assert_eq!( decode_charset( b"caf\xC3\xA9", "UTF-8" ),			"café" );
+		assert_eq!( decode_charset( b"caf\xE9", "utf-8" ),				"caf�" );
+		assert_eq!( decode_charset( b"caf\xE9", "latin1" ),				"café" );
+		assert_eq!( decode_charset( b"\xF8", "ISO-8859-8" ),			"ר" );
+		assert_eq!( decode_charset( b"\xB3\xC9\xB9\xA6", "GB2312" ),	"成功" );
+		assert_eq!( decode_charset( b"caf\xE9", "zalgo-he-comes" ),		"café" );
+		assert_eq!( decode_charset( b"caf+AOk-", "UTF-7" ),				"café" );
+	}
+}
@@ -0,0 +1,386 @@
+// One interface over every escapement, so code can be generic over which one it undoes
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+
+#[cfg( feature = "std" )]
+use stream::{Escape, Escaper};
+use {decode_base16_bytes, decode_base32_bytes, decode_base64_bytes, decode_base85_bytes, encode_base16, encode_base32, encode_base64};
+use {decode_entities, decode_entities_with, encode_entities, encode_entities_with, decode_quoted_printable_bytes, decode_rfc1738, encode_rfc1738};
+use {decode_json_string, encode_json_string, decode_literal, encode_literal, decode_idna, encode_idna};
+use {decode_utf7, encode_utf7, decode_imap_utf7, encode_imap_utf7, decode_xml, encode_html};
+#[cfg( feature = "std" )]
+use decode_rfc2047;
+use {Base32, Base64, Base85, Dialect, DecodeError, EncodeSet, EntitySet, HtmlContext, JsonEscape, XmlOptions};
+
+/// An escapement that can be undone
+///
+/// Implemented by the marker types such as `Entities` and `Percent`, and by
+/// the option types the free functions already take, such as `EntitySet`,
+/// `Base64` or `XmlOptions`, which decode as their function would.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{Decode, Entities, Percent};
+/// fn clean<D: Decode>( codec: D, field: &str ) -> String {
+///     codec.decode( field ).map( |decoded| decoded.into_owned( ) ).unwrap_or_else( |_| field.to_string( ) )
+/// }
+/// assert_eq!( clean( Entities, "Fish &amp; Chips" ), "Fish & Chips" );
+/// assert_eq!( clean( Percent, "Fish%20%26%20Chips" ), "Fish & Chips" );
+/// ```
+pub trait Decode {
+	/// Undoes the escapement in `s`, borrowing it when there was nothing to undo
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError>;
+}
+
+/// An escapement that can be applied
+///
+/// # Examples
+///
+/// ```
+/// use mung::{Encode, EncodeSet, Entities};
+/// assert_eq!( Entities.encode( "<b>" ), "&lt;b&gt;" );
+/// assert_eq!( EncodeSet::Component.encode( "a b" ), "a%20b" );
+/// ```
+pub trait Encode {
+	/// Escapes `s`, borrowing it when nothing needed escaping
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str>;
+}
+
+/// Two codecs applied one after the other, first `.0` and then `.1`
+///
+/// # Examples
+///
+/// ```
+/// use mung::{Chain, Decode, Encode, Entities, Percent};
+/// assert_eq!( Chain( Percent, Entities ).decode( "%26amp%3B" ).unwrap( ), "&" );
+/// assert_eq!( Chain( Entities, Percent ).encode( "&" ), "%26amp%3B" );
+/// ```
+#[derive( Clone, Copy, Debug, Default, PartialEq, Eq )]
+pub struct Chain<A, B>( pub A, pub B );
+
+impl<A: Decode, B: Decode> Decode for Chain<A, B> {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( match self.0.decode( s )? {
+			Cow::Borrowed( s ) => self.1.decode( s )?,
+			Cow::Owned( s ) => self.1.decode( &s )?.into_owned( ).into( ),
+		} )
+	}
+}
+
+impl<A: Encode, B: Encode> Encode for Chain<A, B> {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		match self.0.encode( s ) {
+			Cow::Borrowed( s ) => self.1.encode( s ),
+			Cow::Owned( s ) => self.1.encode( &s ).into_owned( ).into( ),
+		}
+	}
+}
+
+impl<T: Decode + ?Sized> Decode for &T {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		( **self ).decode( s )
+	}
+}
+
+impl<T: Decode + ?Sized> Decode for Box<T> {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		( **self ).decode( s )
+	}
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		( **self ).encode( s )
+	}
+}
+
+impl<T: Encode + ?Sized> Encode for Box<T> {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		( **self ).encode( s )
+	}
+}
+
+/// Reads decoded bytes as UTF-8, as the text codecs must; U+FFFD stands in for the rest
+fn utf8<'a>( bytes: &[u8] ) -> Cow<'a, str> {
+	String::from_utf8_lossy( bytes ).into_owned( ).into( )
+}
+
+/// SGML, XML and HTML entities, as `decode_entities` and `encode_entities` treat them
+#[derive( Clone, Copy, Debug, Default, PartialEq, Eq )]
+pub struct Entities;
+
+impl Decode for Entities {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( decode_entities( s ) )
+	}
+}
+
+impl Encode for Entities {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_entities( s )
+	}
+}
+
+impl<'e> Decode for EntitySet<'e> {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( decode_entities_with( s, *self ) )
+	}
+}
+
+impl<'e> Encode for EntitySet<'e> {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_entities_with( s, *self )
+	}
+}
+
+/// XML with its DOCTYPE's own entities, as `decode_xml` reads it
+impl Decode for XmlOptions {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		decode_xml( s, *self )
+	}
+}
+
+/// Escapes for the given place in an HTML page, as `encode_html` does
+impl Encode for HtmlContext {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_html( s, *self )
+	}
+}
+
+/// URL percent escapes; encoding leaves only `EncodeSet::Component` alone
+#[derive( Clone, Copy, Debug, Default, PartialEq, Eq )]
+pub struct Percent;
+
+impl Decode for Percent {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( decode_rfc1738( s ) )
+	}
+}
+
+impl Encode for Percent {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_rfc1738( s, EncodeSet::Component )
+	}
+}
+
+impl Encode for EncodeSet {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_rfc1738( s, *self )
+	}
+}
+
+/// MIME quoted-printable, carrying UTF-8
+#[derive( Clone, Copy, Debug, Default, PartialEq, Eq )]
+pub struct QuotedPrintable;
+
+impl Decode for QuotedPrintable {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( match decode_quoted_printable_bytes( s.as_bytes( ) ) {
+			Cow::Borrowed( _ ) => s.into( ),
+			Cow::Owned( allo ) => utf8( &allo ),
+		} )
+	}
+}
+
+#[cfg( feature = "std" )]
+impl Encode for QuotedPrintable {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		match Escaper::new( Escape::QuotedPrintable ).encode( s.as_bytes( ), true ) {
+			ref allo if allo[ ..] == *s.as_bytes( ) => s.into( ),
+			allo => utf8( &allo ),
+		}
+	}
+}
+
+/// The encoded words of mail headers, as `decode_rfc2047` reads them
+#[cfg( feature = "std" )]
+#[derive( Clone, Copy, Debug, Default, PartialEq, Eq )]
+pub struct Rfc2047;
+
+#[cfg( feature = "std" )]
+impl Decode for Rfc2047 {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( decode_rfc2047( s ) )
+	}
+}
+
+/// JSON string escapes; encoding escapes as `JsonEscape::Minimal`
+#[derive( Clone, Copy, Debug, Default, PartialEq, Eq )]
+pub struct Json;
+
+impl Decode for Json {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( decode_json_string( s ) )
+	}
+}
+
+impl Encode for Json {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_json_string( s, JsonEscape::Minimal )
+	}
+}
+
+impl Encode for JsonEscape {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_json_string( s, *self )
+	}
+}
+
+impl Decode for Dialect {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( decode_literal( s, *self ) )
+	}
+}
+
+impl Encode for Dialect {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_literal( s, *self )
+	}
+}
+
+/// Internationalised domain names, label by label, as `decode_idna` and `encode_idna` treat them
+#[derive( Clone, Copy, Debug, Default, PartialEq, Eq )]
+pub struct Idna;
+
+impl Decode for Idna {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( decode_idna( s ) )
+	}
+}
+
+impl Encode for Idna {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_idna( s )
+	}
+}
+
+/// UTF-7, as mail once carried it
+#[derive( Clone, Copy, Debug, Default, PartialEq, Eq )]
+pub struct Utf7;
+
+impl Decode for Utf7 {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( decode_utf7( s ) )
+	}
+}
+
+impl Encode for Utf7 {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_utf7( s )
+	}
+}
+
+/// The modified UTF-7 of IMAP mailbox names
+#[derive( Clone, Copy, Debug, Default, PartialEq, Eq )]
+pub struct ImapUtf7;
+
+impl Decode for ImapUtf7 {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		Ok( decode_imap_utf7( s ) )
+	}
+}
+
+impl Encode for ImapUtf7 {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_imap_utf7( s )
+	}
+}
+
+/// Hex, carrying UTF-8
+#[derive( Clone, Copy, Debug, Default, PartialEq, Eq )]
+pub struct Base16;
+
+impl Decode for Base16 {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		decode_base16_bytes( s.as_bytes( ) ).map( |bytes| utf8( &bytes ) )
+	}
+}
+
+impl Encode for Base16 {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_base16( s.as_bytes( ) ).into( )
+	}
+}
+
+/// Base32 carrying UTF-8; use `decode_base32` for other charsets
+impl Decode for Base32 {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		decode_base32_bytes( s.as_bytes( ), *self ).map( |bytes| utf8( &bytes ) )
+	}
+}
+
+impl Encode for Base32 {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_base32( s.as_bytes( ), *self ).into( )
+	}
+}
+
+/// Base64 carrying UTF-8; use `decode_base64` for other charsets
+impl Decode for Base64 {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		decode_base64_bytes( s.as_bytes( ), *self ).map( |bytes| utf8( &bytes ) )
+	}
+}
+
+impl Encode for Base64 {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		encode_base64( s.as_bytes( ), *self ).into( )
+	}
+}
+
+/// Base85 carrying UTF-8; there is no `Encode`, as Z85 cannot encode every length
+impl Decode for Base85 {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		decode_base85_bytes( s.as_bytes( ), *self ).map( |bytes| utf8( &bytes ) )
+	}
+}
+
+
+#[cfg( test )]
+mod tests {
+
+	use super::*;
+
+	fn round_trip<C: Decode + Encode>( codec: C, s: &str ) -> String {
+		codec.decode( &codec.encode( s ) ).unwrap( ).into_owned( )
+	}
+
+	#[test]
+	fn test_codecs( ) {
+		let plain = "<a href=\"?q=fish & chips\">café ♥</a>";
+		assert_eq!( round_trip( Entities, plain ),				plain );
+		assert_eq!( round_trip( EntitySet::Html5, plain ),		plain );
+		assert_eq!( round_trip( Percent, plain ),				plain );
+		#[cfg( feature = "std" )]
+		assert_eq!( round_trip( QuotedPrintable, plain ),		plain );
+		assert_eq!( round_trip( Json, plain ),					plain );
+		assert_eq!( round_trip( Dialect::Python, plain ),		plain );
+		assert_eq!( round_trip( Utf7, plain ),					plain );
+		assert_eq!( round_trip( ImapUtf7, plain ),				plain );
+		assert_eq!( round_trip( Base16, plain ),				plain );
+		assert_eq!( round_trip( Base32::Hex, plain ),			plain );
+		assert_eq!( round_trip( Base64::UrlSafe, plain ),		plain );
+		assert_eq!( round_trip( Idna, "bücher.example" ),		"bücher.example" );
+
+		#[cfg( feature = "std" )]
+		assert_eq!( Rfc2047.decode( "=?ISO-8859-1?Q?Andr=E9?=" ),	Ok( "André".into( ) ) );
+		assert_eq!( Base85::Ascii85.decode( "9jqo^~>" ),			Ok( "Man ".into( ) ) );
+		assert_eq!( Base64::Standard.decode( "Zh=" ),				Err( DecodeError::InvalidPadding { offset: 2 } ) );
+		assert_eq!( XmlOptions { strict: true, ..XmlOptions::default( ) }.decode( "&fred;" ),	Err( DecodeError::UndeclaredEntity { offset: 0, name: "fred".into( ), suggestion: None } ) );
+		assert_eq!( HtmlContext::Url.encode( "javascript:alert(1)" ),	"about:invalid" );
+	}
+
+	#[test]
+	fn test_chain( ) {
+		let pipeline: Chain<Box<dyn Decode>, Box<dyn Decode>> = Chain( Box::new( Base64::Standard ), Box::new( Entities ) );
+		assert_eq!( pipeline.decode( "RmlzaCAmYW1wOyBDaGlwcw==" ),	Ok( "Fish & Chips".into( ) ) );
+		assert_eq!( Chain( &Percent, Chain( Percent, Entities ) ).decode( "%2526amp%253B" ),	Ok( "&".into( ) ) );
+
+		let unchanged = Chain( Percent, Entities ).decode( "Fish and Chips" ).unwrap( );
+		assert!( match unchanged { Cow::Borrowed( _ ) => true, Cow::Owned( _ ) => false } );
+		assert_eq!( Chain( Entities, Base64::Standard ).encode( "&" ),	"JmFtcDs=" );
+	}
+}
@@ -0,0 +1,317 @@
+// Which entity names a document may use, as each markup language defines its own
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use builtin_entity;
+use ENTITIES;
+use html5::HTML5;
+use xml::entity_declarations;
+
+/// The vocabulary of named entities understood by `decode_entities_with` and
+/// written by `encode_entities_with`
+///
+/// Numeric references such as `&#38;` are understood in every set.
+#[derive( Clone, Copy, Debug, PartialEq, Eq )]
+pub enum EntitySet<'a> {
+	/// Only the five XML predefines: `amp`, `lt`, `gt`, `quot` and `apos`
+	/// See: https://www.w3.org/TR/xml/#sec-predefined-ent
+	Xml,
+	/// The 252 entities of HTML 4, which lack `apos`
+	/// See: https://www.w3.org/TR/html4/sgml/entities.html
+	Html4,
+	/// HTML 4 and `apos`; though an encoder avoids `apos`, which HTML 4 user agents may not know
+	/// See: https://www.w3.org/TR/xhtml1/#C_16
+	Xhtml1,
+	/// The 2125 named references of HTML 5
+	/// See: https://html.spec.whatwg.org/multipage/named-characters.html
+	Html5,
+	/// Only the given names and their replacements
+	Custom( &'a [( &'a str, &'a str )] ),
+	/// The names of an `EntityTable`, then those of `Xhtml1`
+	Table( &'a EntityTable ),
+}
+
+/// Entities registered at runtime, such as the ISO 8879 sets used by DocBook and TEI
+///
+/// A table sits on top of the built-in HTML 4 names: its own entries are
+/// looked up first and the built-in ones fill the gaps. Pass it to
+/// `decode_entities_with` and `encode_entities_with` as `EntitySet::Table`.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{decode_entities_with, EntitySet, EntityTable};
+/// let mut table = EntityTable::from_pairs( &[ ( "corp", "ACME Ltd" ) ] );
+/// table.parse_dtd( r#"<!ENTITY frac12 "&#x000BD;" ><!--=fraction one-half -->"# );
+/// assert_eq!( decode_entities_with( "&corp; &frac12; &amp;", EntitySet::Table( &table ) ), "ACME Ltd ½ &" );
+/// ```
+#[derive( Clone, Debug, Default, PartialEq, Eq )]
+pub struct EntityTable {
+	entities: BTreeMap<String, String>,
+}
+
+impl EntityTable {
+	/// An empty table, which knows only the built-in names
+	pub fn new( ) -> EntityTable {
+		EntityTable::default( )
+	}
+
+	/// A table of the given names and their replacements
+	pub fn from_pairs<K: AsRef<str>, V: AsRef<str>>( pairs: &[( K, V )] ) -> EntityTable {
+		let mut table = EntityTable::new( );
+		for ( name, value ) in pairs {
+			table.insert( name.as_ref( ), value.as_ref( ) );
+		}
+		table
+	}
+
+	/// Adds an entity, replacing any earlier one of that name
+	pub fn insert( &mut self, name: &str, value: &str ) {
+		self.entities.insert( name.to_string( ), value.to_string( ) );
+	}
+
+	/// Adds the general entities declared in an SGML or XML entity file
+	///
+	/// Both `<!ENTITY half "&#189;">` and SGML's `<!ENTITY half CDATA "&#189;">`
+	/// are read; `SDATA` values that only name a glyph, such as `"[half ]"`,
+	/// and external or parameter entities are skipped. Where the file declares
+	/// a name twice the first declaration wins, as it would in the document,
+	/// but the file's names replace those already in the table.
+	pub fn parse_dtd( &mut self, s: &str ) {
+		let mut entities = BTreeMap::new( );
+		let mut at = 0;
+		while at < s.len( ) {
+			let ( declared, end ) = entity_declarations( &s[ at..] );
+			for ( name, value ) in declared {
+				entities.entry( name ).or_insert( value );
+			}
+			// Step over the close of a marked section, and read on
+			at += end + 1;
+		}
+		self.entities.extend( entities );
+	}
+
+	/// The replacement for the entity called `name`, from this table or the built-in names
+	pub fn get( &self, name: &str ) -> Option<&str> {
+		match self.entities.get( name ) {
+			Some( value ) => Some( value ),
+			None => builtin_entity( name ),
+		}
+	}
+
+	/// How many entities were registered, not counting the built-in ones
+	pub fn len( &self ) -> usize {
+		self.entities.len( )
+	}
+
+	/// Whether no entities were registered
+	pub fn is_empty( &self ) -> bool {
+		self.entities.is_empty( )
+	}
+}
+
+/// The markup-significant characters, and their usual names
+static MARKUP: &[( char, &str )] = &[ ( '&', "amp" ), ( '<', "lt" ), ( '>', "gt" ), ( '"', "quot" ), ( '\'', "apos" ) ];
+
+impl<'a> EntitySet<'a> {
+	/// The replacement for the entity called `name`, if this set has one
+	pub(crate) fn get( &self, name: &str ) -> Option<&'a str> {
+		match *self {
+			EntitySet::Xml => MARKUP.iter( ).find( |&&( _, known )| known == name ).map( |&( _, known )| match known {
+				"amp" => "&", "lt" => "<", "gt" => ">", "quot" => "\"", _ => "'",
+			} ),
+			EntitySet::Html4 if name == "apos" => None,
+			EntitySet::Html4 | EntitySet::Xhtml1 => builtin_entity( name ),
+			EntitySet::Html5 => HTML5.binary_search_by( |&( known, _ )| known.cmp( name ) ).ok( ).map( |found| HTML5[ found ].1 ),
+			EntitySet::Custom( pairs ) => pairs.iter( ).find( |&&( known, _ )| known == name ).map( |&( _, value )| value ),
+			EntitySet::Table( table ) => table.get( name ),
+		}
+	}
+
+	/// The name an encoder should write for the markup character `c`, if this set has one
+	pub(crate) fn name_of( &self, c: char ) -> Option<&'a str> {
+		// Like Xhtml1, whose names it falls back on, a table only writes a name for ' that it registered itself
+		match *self {
+			EntitySet::Xhtml1 if c == '\'' => return None,
+			EntitySet::Table( table ) if c == '\'' => return table.entities.iter( ).find( |&( _, known )| known == "'" ).map( |( name, _ )| name.as_str( ) ),
+			_ => { },
+		}
+		let mut buffer = [ 0; 4 ];
+		let value: &str = c.encode_utf8( &mut buffer );
+		if let Some( &( _, name ) ) = MARKUP.iter( ).find( |&&( markup, _ )| markup == c ) {
+			if self.get( name ) == Some( value ) {
+				return Some( name );
+			}
+		}
+		match *self {
+			EntitySet::Custom( pairs ) => pairs.iter( ).find( |&&( _, known )| known == value ).map( |&( name, _ )| name ),
+			EntitySet::Table( table ) => table.entities.iter( ).find( |&( _, known )| known == value ).map( |( name, _ )| name.as_str( ) ),
+			_ => None,
+		}
+	}
+
+	/// Calls `f` with every name this set knows, the ones looked up first coming first
+	fn each_name<F: FnMut( &'a str )>( &self, mut f: F ) {
+		match *self {
+			EntitySet::Xml => MARKUP.iter( ).for_each( |&( _, name )| f( name ) ),
+			EntitySet::Html4 => ENTITIES.iter( ).filter( |&&( name, _ )| name != "apos" ).for_each( |&( name, _ )| f( name ) ),
+			EntitySet::Xhtml1 => ENTITIES.iter( ).for_each( |&( name, _ )| f( name ) ),
+			EntitySet::Html5 => HTML5.iter( ).for_each( |&( name, _ )| f( name ) ),
+			EntitySet::Custom( pairs ) => pairs.iter( ).for_each( |&( name, _ )| f( name ) ),
+			EntitySet::Table( table ) => {
+				table.entities.keys( ).for_each( |name| f( name ) );
+				ENTITIES.iter( ).for_each( |&( name, _ )| f( name ) );
+			},
+		}
+	}
+}
+
+/// The name in `set` closest to the misspelled `name`, if one is close enough
+///
+/// Closeness is the number of letters to add, drop, change or swap with a
+/// neighbour: one for names of up to four letters, two for longer ones.
+/// Where several names are as close, the first the set knows wins.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{suggest_entity, EntitySet};
+/// assert_eq!( suggest_entity( "hellp", EntitySet::Html4 ), Some( "hellip" ) );
+/// assert_eq!( suggest_entity( "heart", EntitySet::Html4 ), Some( "hearts" ) );
+/// assert_eq!( suggest_entity( "fred", EntitySet::Html4 ), None );
+/// ```
+pub fn suggest_entity<'a>( name: &str, set: EntitySet<'a> ) -> Option<&'a str> {
+	let limit = if name.len( ) <= 4 { 1 } else { 2 };
+	let mut best: Option<( usize, &'a str )> = None;
+	set.each_name( |known| {
+		// Only a closer name than the best so far is worth measuring
+		let within = match best {
+			Some( ( 0, _ ) ) => return,
+			Some( ( distance, _ ) ) => distance - 1,
+			None => limit,
+		};
+		if known.len( ) + within < name.len( ) || name.len( ) + within < known.len( ) {
+			return;
+		}
+		let distance = edit_distance( name.as_bytes( ), known.as_bytes( ) );
+		if distance <= within {
+			best = Some( ( distance, known ) );
+		}
+	} );
+	best.map( |( _, known )| known )
+}
+
+/// The optimal string alignment distance between `a` and `b`: Levenshtein's,
+/// where swapping two neighbouring letters also counts as one edit
+fn edit_distance( a: &[u8], b: &[u8] ) -> usize {
+	let mut before: Vec<usize> = Vec::new( );
+	let mut previous: Vec<usize> = ( 0..=b.len( ) ).collect( );
+	for i in 1..=a.len( ) {
+		let mut row = vec![ i; b.len( ) + 1 ];
+		for j in 1..=b.len( ) {
+			let cost = if a[ i - 1 ] == b[ j - 1 ] { 0 } else { 1 };
+			row[ j ] = ( previous[ j ] + 1 ).min( row[ j - 1 ] + 1 ).min( previous[ j - 1 ] + cost );
+			if i > 1 && j > 1 && a[ i - 1 ] == b[ j - 2 ] && a[ i - 2 ] == b[ j - 1 ] {
+				row[ j ] = row[ j ].min( before[ j - 2 ] + 1 );
+			}
+		}
+		before = core::mem::replace( &mut previous, row );
+	}
+	previous[ b.len( ) ]
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+	use encode_entities_with;
+
+	#[test]
+	fn test_entity_set( ) {
+
+		assert_eq!( EntitySet::Xml.get( "apos" ),			Some( "'" ) );
+		assert_eq!( EntitySet::Xml.get( "nbsp" ),			None );
+		assert_eq!( EntitySet::Html4.get( "nbsp" ),			Some( "\u{A0}" ) );
+		assert_eq!( EntitySet::Html4.get( "apos" ),			None );
+		assert_eq!( EntitySet::Xhtml1.get( "apos" ),		Some( "'" ) );
+		assert_eq!( EntitySet::Html5.get( "apos" ),			Some( "'" ) );
+		assert_eq!( EntitySet::Html5.get( "NotNestedGreaterGreater" ),	Some( "\u{2AA2}\u{338}" ) );
+		assert_eq!( EntitySet::Html5.get( "hellip" ),		Some( "…" ) );
+		assert_eq!( EntitySet::Html5.get( "heart" ),		None );
+
+		let house = [ ( "corp", "ACME" ), ( "squo", "'" ) ];
+		assert_eq!( EntitySet::Custom( &house ).get( "corp" ),	Some( "ACME" ) );
+		assert_eq!( EntitySet::Custom( &house ).get( "amp" ),	None );
+	}
+
+	#[test]
+	fn test_entity_set_name_of( ) {
+
+		assert_eq!( EntitySet::Xml.name_of( '\'' ),			Some( "apos" ) );
+		assert_eq!( EntitySet::Html4.name_of( '\'' ),		None );
+		assert_eq!( EntitySet::Xhtml1.name_of( '\'' ),		None );
+		assert_eq!( EntitySet::Html5.name_of( '\'' ),		Some( "apos" ) );
+		assert_eq!( EntitySet::Html5.name_of( '&' ),		Some( "amp" ) );
+		assert_eq!( EntitySet::Custom( &[ ( "squo", "'" ) ] ).name_of( '\'' ),	Some( "squo" ) );
+		assert_eq!( EntitySet::Custom( &[ ( "squo", "'" ) ] ).name_of( '&' ),	None );
+
+		let table = EntityTable::from_pairs( &[ ( "squo", "'" ) ] );
+		assert_eq!( EntitySet::Table( &table ).name_of( '\'' ),	Some( "squo" ) );
+		assert_eq!( EntitySet::Table( &table ).name_of( '<' ),		Some( "lt" ) );
+		assert_eq!( EntitySet::Table( &EntityTable::new( ) ).name_of( '\'' ),	None );
+	}
+
+	#[test]
+	fn test_suggest_entity( ) {
+
+		assert_eq!( edit_distance( b"hellp", b"hellip" ),		1 );
+		assert_eq!( edit_distance( b"hlelip", b"hellip" ),		1 );
+		assert_eq!( edit_distance( b"", b"amp" ),				3 );
+		assert_eq!( suggest_entity( "hellp", EntitySet::Html5 ),	Some( "hellip" ) );
+		assert_eq!( suggest_entity( "heart", EntitySet::Html5 ),	Some( "hearts" ) );
+		assert_eq!( suggest_entity( "cpoy", EntitySet::Html4 ),		Some( "copy" ) );
+		assert_eq!( suggest_entity( "nbsp", EntitySet::Xml ),		None );
+		assert_eq!( suggest_entity( "ap", EntitySet::Xml ),			Some( "amp" ) );
+		assert_eq!( suggest_entity( "amp", EntitySet::Xml ),		Some( "amp" ) );
+		assert_eq!( suggest_entity( "crop", EntitySet::Custom( &[ ( "corp", "ACME" ) ] ) ),	Some( "corp" ) );
+
+		let table = EntityTable::from_pairs( &[ ( "frac12", "½" ) ] );
+		assert_eq!( suggest_entity( "frac21", EntitySet::Table( &table ) ),	Some( "frac12" ) );
+	}
+
+	#[test]
+	fn test_entity_table( ) {
+
+		let mut table = EntityTable::from_pairs( &[ ( "corp".to_string( ), "ACME".to_string( ) ) ] );
+		assert_eq!( table.len( ),				1 );
+		assert_eq!( table.get( "corp" ),		Some( "ACME" ) );
+		assert_eq!( table.get( "nbsp" ),		Some( "\u{A0}" ) );
+		assert_eq!( table.get( "missing" ),		None );
+
+		table.insert( "nbsp", " " );
+		assert_eq!( table.get( "nbsp" ),		Some( " " ) );
+
+		// As with Xhtml1, ' is not written as &apos;
+		assert_eq!( encode_entities_with( "it's", EntitySet::Table( &table ) ),	"it&#x27;s" );
+	}
+
+	#[test]
+	fn test_entity_table_parse_dtd( ) {
+
+		// In the style of ISOnum, both the SGML and the XML versions
+		let sgml = "<!-- (C) International Organization for Standardization 1986 -->\n<!ENTITY half   SDATA \"[half  ]\"--=fraction one-half-->\n<!ENTITY frac12 CDATA \"&#189;\"--=fraction one-half-->\n<!ENTITY % ISOnum PUBLIC \"ISO 8879:1986//ENTITIES Numeric and Special Graphic//EN\">\n";
+		let xml = "<!ENTITY frac14           \"&#x000BC;\" ><!--=fraction one-quarter -->\n<![%house;[\n<!ENTITY house \"Ours\">\n]]>\n<!ENTITY frac14 \"not first\">\n<!ENTITY logo SYSTEM \"logo.gif\" NDATA gif>\n";
+
+		let mut table = EntityTable::new( );
+		table.parse_dtd( sgml );
+		table.parse_dtd( xml );
+		assert_eq!( table.get( "frac12" ),		Some( "½" ) );
+		assert_eq!( table.get( "frac14" ),		Some( "¼" ) );
+		assert_eq!( table.get( "house" ),		Some( "Ours" ) );
+		assert_eq!( table.get( "half" ),		None );
+		assert_eq!( table.get( "logo" ),		None );
+		assert_eq!( table.len( ),				3 );
+	}
+}
@@ -0,0 +1,84 @@
+// What can go wrong while undoing an escapement strictly
+
+use alloc::fmt;
+use alloc::string::String;
+#[cfg( feature = "std" )]
+use std::error::Error;
+
+/// Why input could not be decoded
+#[derive( Clone, Debug, PartialEq, Eq )]
+pub enum DecodeError {
+	/// A byte that has no place in the alphabet, and where it was found
+	InvalidByte { offset: usize, byte: u8 },
+	/// The input, once whitespace is set aside, cannot be a whole number of groups
+	InvalidLength { length: usize },
+	/// Padding is missing, misplaced, or present where it is not allowed
+	InvalidPadding { offset: usize },
+	/// The last symbol carries bits that a canonical encoder would have left as zero
+	TrailingBits { offset: usize },
+	/// A group, starting at this offset, stands for more than its bytes can hold
+	Overflow { offset: usize },
+	/// A line that frames the data, such as `begin` or `=yend`, is missing or unreadable
+	MissingLine { line: &'static str },
+	/// A part of a multi-part file is missing
+	MissingPart { part: u32 },
+	/// A part whose name or size differs from the first part's, so belongs to another file
+	ForeignPart { part: u32 },
+	/// The checksum carried with the data does not match it
+	Checksum { expected: u32, actual: u32 },
+	/// An entity reference, at this offset, names an entity that was never declared;
+	/// with the closest declared name, if one is close enough to be a misspelling
+	UndeclaredEntity { offset: usize, name: String, suggestion: Option<String> },
+	/// Expanding the entity reference at this offset nests too deeply or grows too large
+	ExpansionLimit { offset: usize },
+}
+
+impl fmt::Display for DecodeError {
+	fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result {
+		match *self {
+			DecodeError::InvalidByte { offset, byte } =>
+				write!( f, "invalid byte 0x{:02X} at offset {}", byte, offset ),
+			DecodeError::InvalidLength { length } =>
+				write!( f, "invalid length {}", length ),
+			DecodeError::InvalidPadding { offset } =>
+				write!( f, "invalid padding at offset {}", offset ),
+			DecodeError::TrailingBits { offset } =>
+				write!( f, "non-zero trailing bits at offset {}", offset ),
+			DecodeError::Overflow { offset } =>
+				write!( f, "group at offset {} overflows", offset ),
+			DecodeError::MissingLine { line } =>
+				write!( f, "missing {} line", line ),
+			DecodeError::MissingPart { part } =>
+				write!( f, "missing part {}", part ),
+			DecodeError::ForeignPart { part } =>
+				write!( f, "part {} belongs to another file", part ),
+			DecodeError::Checksum { expected, actual } =>
+				write!( f, "checksum {:08x} does not match {:08x}", actual, expected ),
+			DecodeError::UndeclaredEntity { offset, ref name, suggestion: Some( ref suggestion ) } =>
+				write!( f, "undeclared entity &{}; at offset {}, did you mean &{};?", name, offset, suggestion ),
+			DecodeError::UndeclaredEntity { offset, ref name, suggestion: None } =>
+				write!( f, "undeclared entity &{}; at offset {}", name, offset ),
+			DecodeError::ExpansionLimit { offset } =>
+				write!( f, "entity at offset {} expands beyond the limits", offset ),
+		}
+	}
+}
+
+#[cfg( feature = "std" )]
+impl Error for DecodeError { }
+
+/// A codec name that a `Registry` does not know
+#[derive( Clone, Debug, PartialEq, Eq )]
+pub struct UnknownCodec {
+	/// The name, as it was asked for
+	pub name: String,
+}
+
+impl fmt::Display for UnknownCodec {
+	fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result {
+		write!( f, "unknown codec {:?}", self.name )
+	}
+}
+
+#[cfg( feature = "std" )]
+impl Error for UnknownCodec { }
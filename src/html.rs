@@ -0,0 +1,361 @@
+// Reading HTML the way a browser would show it, as plain text
+
+use alloc::borrow::Cow;
+use alloc::fmt::Write;
+use alloc::string::String;
+
+use {decode_entities_single, encode_entities, encode_rfc1738, EncodeSet, EntitySet};
+
+/// Where in an HTML page untrusted text is about to be placed, for `encode_html`
+/// See: OWASP Cross Site Scripting Prevention https://cheatsheetseries.owasp.org/cheatsheets/Cross_Site_Scripting_Prevention_Cheat_Sheet.html
+#[derive( Clone, Copy, Debug, PartialEq, Eq )]
+pub enum HtmlContext {
+	/// Between tags, such as `<p>…</p>`
+	Text,
+	/// Inside a quoted attribute value, such as `title="…"`
+	Attribute,
+	/// Inside an attribute value with no quotes, such as `width=…`
+	UnquotedAttribute,
+	/// A whole URL inside a quoted `href` or `src`; only `http`, `https` and `mailto` are allowed
+	Url,
+	/// JSON text inside `<script type="application/json">`
+	Script,
+	/// Inside a `<style>` block or `style` attribute, as a CSS value
+	Style,
+}
+
+/// What a URL with a scheme not on the allowlist becomes
+static UNSAFE_URL: &str = "about:invalid";
+
+/// Schemes a URL attribute may use
+static SAFE_SCHEMES: &[&str] = &[ "http", "https", "mailto" ];
+
+/// Elements whose content is never rendered as text
+static HIDDEN: &[&str] = &[ "script", "style", "template" ];
+
+/// Elements that start on a new line
+static BLOCKS: &[&str] = &[
+	"address", "article", "aside", "blockquote", "caption", "dd", "details", "dialog", "div", "dl", "dt",
+	"fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header",
+	"hr", "li", "main", "nav", "ol", "p", "pre", "section", "summary", "table", "tbody", "tfoot",
+	"thead", "title", "tr", "ul",
+];
+
+/// Elements that sit side by side, but apart
+static CELLS: &[&str] = &[ "td", "th" ];
+
+/// Encodes untrusted text so it can be placed safely in `context`
+///
+/// `Text` and `Attribute` use `encode_entities`. `UnquotedAttribute` encodes
+/// every character below U+0100 but ASCII letters and digits as `&#xHH;`, as
+/// OWASP advises. `Url` keeps
+/// relative URLs and those with an allowed scheme, percent-encodes what a
+/// URL may not hold and then entity-encodes the lot; any other URL, such as
+/// `javascript:`, becomes `about:invalid`. `Script` escapes `<`, `>`, `&`,
+/// U+2028 and U+2029 as JSON `\uXXXX`, so the data can neither close the
+/// element nor break older JavaScript parsers. `Style` encodes the same
+/// characters as `UnquotedAttribute`, as CSS `\HH ` escapes.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{encode_html, HtmlContext};
+/// assert_eq!( encode_html( "<b>Tom & Jerry</b>", HtmlContext::Text ), "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;" );
+/// assert_eq!( encode_html( "1 onmouseover=x", HtmlContext::UnquotedAttribute ), "1&#x20;onmouseover&#x3d;x" );
+/// assert_eq!( encode_html( "javascript:alert(1)", HtmlContext::Url ), "about:invalid" );
+/// assert_eq!( encode_html( r#"{"a":"</script>"}"#, HtmlContext::Script ), r#"{"a":"\u003c/script\u003e"}"# );
+/// ```
+pub fn encode_html<'a>( s: &'a str, context: HtmlContext ) -> Cow<'a, str> {
+
+	match context {
+		HtmlContext::Text | HtmlContext::Attribute => encode_entities( s ),
+		HtmlContext::UnquotedAttribute => encode_latin1( s, |allo, byte| { let _ = write!( allo, "&#x{:x};", byte ); } ),
+		HtmlContext::Style => encode_latin1( s, |allo, byte| { let _ = write!( allo, "\\{:x} ", byte ); } ),
+		HtmlContext::Url => {
+			if !safe_url( s ) {
+				return UNSAFE_URL.into( );
+			}
+			match encode_rfc1738( s, EncodeSet::Url ) {
+				Cow::Borrowed( _ ) => encode_entities( s ),
+				Cow::Owned( encoded ) => encode_entities( &encoded ).into_owned( ).into( ),
+			}
+		},
+		HtmlContext::Script => {
+			if !s.contains( &[ '<', '>', '&', '\u{2028}', '\u{2029}' ][ ..] ) {
+				return s.into( );
+			}
+			let mut allo = String::with_capacity( s.len( ) + 16 );
+			for c in s.chars( ) {
+				match c {
+					'<' | '>' | '&' | '\u{2028}' | '\u{2029}' => { let _ = write!( allo, "\\u{:04x}", c as u32 ); },
+					c => allo.push( c ),
+				}
+			}
+			allo.into( )
+		},
+	}
+}
+
+/// Whether OWASP would have `c` escaped outside quotes: below U+0100, and no ASCII letter or digit
+fn is_latin1_unsafe( c: char ) -> bool {
+	( c as u32 ) < 0x100 && !c.is_ascii_alphanumeric( )
+}
+
+/// Escapes every character below U+0100 but ASCII letters and digits with `escape`
+fn encode_latin1<'a, F: Fn( &mut String, u8 )>( s: &'a str, escape: F ) -> Cow<'a, str> {
+
+	if !s.chars( ).any( is_latin1_unsafe ) {
+		return s.into( );
+	}
+
+	let mut allo = String::with_capacity( s.len( ) * 2 );
+	for c in s.chars( ) {
+		if is_latin1_unsafe( c ) {
+			escape( &mut allo, c as u8 );
+		} else {
+			allo.push( c );
+		}
+	}
+
+	allo.into( )
+}
+
+/// Whether a URL is relative or uses a scheme on the allowlist
+fn safe_url( s: &str ) -> bool {
+	// Browsers drop these before reading the scheme, so we must too
+	let cleaned: String = s.chars( ).filter( |c| !c.is_ascii_whitespace( ) && !c.is_ascii_control( ) ).collect( );
+	let scheme_end = match cleaned.find( &[ ':', '/', '?', '#' ][ ..] ) {
+		Some( end ) if cleaned[ end..].starts_with( ':' ) => end,
+		_ => return true,
+	};
+	let scheme = cleaned[ ..scheme_end ].to_ascii_lowercase( );
+	SAFE_SCHEMES.contains( &scheme.as_str( ) )
+}
+
+/// Gathers text, collapsing white space as CSS `white-space: normal` does
+struct Text {
+	allo: String,
+	pending_space: bool,
+	line_start: bool,
+	preformatted: usize,
+}
+
+impl Text {
+	fn push_str( &mut self, text: &str ) {
+		for c in text.chars( ) {
+			if self.preformatted > 0 {
+				self.allo.push( c );
+				self.line_start = c == '\n';
+			} else if c == ' ' || c == '\t' || c == '\n' || c == '\r' || c == '\u{C}' {
+				self.pending_space = true;
+			} else {
+				if self.pending_space && !self.line_start {
+					self.allo.push( ' ' );
+				}
+				self.pending_space = false;
+				self.line_start = false;
+				self.allo.push( c );
+			}
+		}
+	}
+
+	fn block( &mut self ) {
+		self.pending_space = false;
+		if !self.allo.is_empty( ) && !self.line_start {
+			self.allo.push( '\n' );
+			self.line_start = true;
+		}
+	}
+
+	fn line_break( &mut self ) {
+		self.pending_space = false;
+		self.allo.push( '\n' );
+		self.line_start = true;
+	}
+}
+
+/// Finds the `>` that closes a tag, stepping over quoted attribute values
+fn tag_end( s: &str ) -> Option<usize> {
+	let mut quote = None;
+	for ( i, byte ) in s.bytes( ).enumerate( ) {
+		match ( quote, byte ) {
+			( None, b'"' ) | ( None, b'\'' ) => quote = Some( byte ),
+			( Some( open ), _ ) if open == byte => quote = None,
+			( None, b'>' ) => return Some( i ),
+			_ => { }
+		}
+	}
+	None
+}
+
+/// Finds `</name` in `s`, ignoring case
+fn closing_tag( s: &str, name: &str ) -> Option<usize> {
+	let bytes = s.as_bytes( );
+	( 0..bytes.len( ) ).find( |&i| {
+		bytes[ i ] == b'<' && bytes.get( i + 1 ) == Some( &b'/' ) &&
+			bytes.len( ) >= i + 2 + name.len( ) && bytes[ i + 2..i + 2 + name.len( ) ].eq_ignore_ascii_case( name.as_bytes( ) )
+	} )
+}
+
+/// Extracts the readable text from an HTML fragment
+///
+/// Tags, comments, and the content of `<script>` and `<style>` are dropped.
+/// Block elements start a new line, `<br>` always breaks one, and white space
+/// collapses as a browser renders it, except inside `<pre>`. Entities are
+/// decoded last, and only once, after the markup is gone; so `&lt;b&gt;`
+/// comes out as the text `<b>` and is never mistaken for a tag, while
+/// `&amp;lt;` comes out as the text `&lt;`.
+///
+/// # Examples
+///
+/// Can be called with `&' str`
+///
+/// ```
+/// use mung::html_to_text;
+/// let text = html_to_text( "<h1>Best &amp;\n   Worst</h1><p>of <b>times</b><br>and more</p>" );
+/// assert_eq!( text, "Best & Worst\nof times\nand more" );
+/// ```
+pub fn html_to_text<'a>( s: &'a str ) -> Cow<'a, str> {
+
+	let mut text = Text { allo: String::with_capacity( s.len( ) ), pending_space: false, line_start: true, preformatted: 0 };
+	let mut rest = s;
+
+	while let Some( found ) = rest.find( '<' ) {
+		text.push_str( &rest[ ..found ] );
+		rest = &rest[ found..];
+
+		if rest.starts_with( "<!--" ) {
+			rest = match rest[ 4..].find( "-->" ) {
+				Some( end ) => &rest[ 4 + end + 3..],
+				None => "",
+			};
+			continue;
+		}
+		if rest.starts_with( "<!" ) || rest.starts_with( "<?" ) {
+			rest = match rest.find( '>' ) {
+				Some( end ) => &rest[ end + 1..],
+				None => "",
+			};
+			continue;
+		}
+
+		let closing = rest.starts_with( "</" );
+		let name_at = if closing { 2 } else { 1 };
+		let name_length = rest[ name_at..].bytes( ).take_while( |byte| byte.is_ascii_alphanumeric( ) ).count( );
+		if name_length == 0 || !rest.as_bytes( )[ name_at ].is_ascii_alphabetic( ) {
+			// A lone < is just text
+			text.push_str( "<" );
+			rest = &rest[ 1..];
+			continue;
+		}
+		let name = rest[ name_at..name_at + name_length ].to_ascii_lowercase( );
+		rest = match tag_end( rest ) {
+			Some( end ) => &rest[ end + 1..],
+			None => "",
+		};
+
+		if !closing && HIDDEN.contains( &name.as_str( ) ) {
+			rest = match closing_tag( rest, &name ) {
+				Some( end ) => match tag_end( &rest[ end..] ) {
+					Some( close ) => &rest[ end + close + 1..],
+					None => "",
+				},
+				None => "",
+			};
+			continue;
+		}
+
+		if name == "br" {
+			text.line_break( );
+		} else if BLOCKS.contains( &name.as_str( ) ) {
+			text.block( );
+			if name == "pre" {
+				if closing {
+					text.preformatted = text.preformatted.saturating_sub( 1 );
+				} else {
+					text.preformatted += 1;
+				}
+			}
+		} else if CELLS.contains( &name.as_str( ) ) {
+			text.pending_space = true;
+		}
+	}
+	text.push_str( rest );
+
+	let allo = text.allo.trim_end_matches( '\n' );
+	match decode_entities_single( allo, EntitySet::Xhtml1 ) {
+		Cow::Borrowed( unchanged ) if unchanged == s => s.into( ),
+		decoded => decoded.into_owned( ).into( ),
+	}
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_html_to_text( ) {
+
+		assert_eq!( html_to_text( "" ),											"" );
+		assert_eq!( html_to_text( "plain text" ),								"plain text" );
+		assert_eq!( html_to_text( "  lots   of\n\tspace  " ),					"lots of space" );
+		assert_eq!( html_to_text( "<b>bold</b> and <i>italic</i>" ),			"bold and italic" );
+		assert_eq!( html_to_text( "a<br>b<br/>c<BR >d" ),						"a\nb\nc\nd" );
+		assert_eq!( html_to_text( "<p>one</p><p>two</p>" ),					"one\ntwo" );
+		assert_eq!( html_to_text( "<div><div><p>nested</p></div></div>tail" ),	"nested\ntail" );
+		assert_eq!( html_to_text( "<ul>\n  <li>one</li>\n  <li>two</li>\n</ul>" ),	"one\ntwo" );
+		assert_eq!( html_to_text( "<table><tr><td>a</td><td>b</td></tr></table>" ),	"a b" );
+		assert_eq!( html_to_text( "<pre>  keep\n   this</pre>after" ),			"  keep\n   this\nafter" );
+	}
+
+	#[test]
+	fn test_html_to_text_hidden( ) {
+
+		assert_eq!( html_to_text( "a<!-- <p>hidden</p> -->b" ),				"ab" );
+		assert_eq!( html_to_text( "<!DOCTYPE html><html><body>hi</body></html>" ),	"hi" );
+		assert_eq!( html_to_text( "<script>if (a < b) { x = '</p>'; }</script>shown" ),	"shown" );
+		assert_eq!( html_to_text( "<STYLE type=\"text/css\">p { color: red }</Style>shown" ),	"shown" );
+		assert_eq!( html_to_text( "<a href=\"x>y\" title='a>b'>link</a>" ),	"link" );
+		assert_eq!( html_to_text( "1 < 2 and 3 > 2" ),							"1 < 2 and 3 > 2" );
+	}
+
+	#[test]
+	fn test_html_to_text_entities( ) {
+
+		assert_eq!( html_to_text( "Best &amp; the Worst" ),					"Best & the Worst" );
+		assert_eq!( html_to_text( "&lt;b&gt;not bold&lt;/b&gt;" ),				"<b>not bold</b>" );
+		assert_eq!( html_to_text( "a&nbsp;&nbsp; b" ),							"a\u{A0}\u{A0} b" );
+		assert_eq!( html_to_text( "<p>caf&eacute;</p>" ),						"café" );
+		assert_eq!( html_to_text( "Use &amp;lt;b&amp;gt; for bold" ),			"Use &lt;b&gt; for bold" );
+		assert_eq!( html_to_text( "<p>&amp;amp;</p>" ),							"&amp;" );
+		assert_eq!( html_to_text( "&#38;#38;" ),								"&#38;" );
+	}
+
+	#[test]
+	fn test_encode_html( ) {
+
+		assert_eq!( encode_html( "plain", HtmlContext::Text ),						"plain" );
+		assert_eq!( encode_html( "\"'><script>", HtmlContext::Attribute ),			"&quot;&#x27;&gt;&lt;script&gt;" );
+		assert_eq!( encode_html( "x onclick=y", HtmlContext::UnquotedAttribute ),	"x&#x20;onclick&#x3d;y" );
+		assert_eq!( encode_html( "é\u{a0}x", HtmlContext::UnquotedAttribute ),		"&#xe9;&#xa0;x" );
+		assert_eq!( encode_html( "ÿĀ中", HtmlContext::UnquotedAttribute ),			"&#xff;Ā中" );
+		assert_eq!( encode_html( "café\u{a0}", HtmlContext::Style ),				"caf\\e9 \\a0 " );
+		assert_eq!( encode_html( "red;}body{", HtmlContext::Style ),				"red\\3b \\7d body\\7b " );
+		assert_eq!( encode_html( "[1,2]", HtmlContext::Script ),					"[1,2]" );
+		assert_eq!( encode_html( "\"a&b\u{2028}\"", HtmlContext::Script ),			"\"a\\u0026b\\u2028\"" );
+	}
+
+	#[test]
+	fn test_encode_html_url( ) {
+
+		assert_eq!( encode_html( "https://example.com/a?b=1&c=2", HtmlContext::Url ),	"https://example.com/a?b=1&amp;c=2" );
+		assert_eq!( encode_html( "/path with space", HtmlContext::Url ),				"/path%20with%20space" );
+		assert_eq!( encode_html( "/already%20done\"", HtmlContext::Url ),				"/already%20done%22" );
+		assert_eq!( encode_html( "MAILTO:me@example.com", HtmlContext::Url ),			"MAILTO:me@example.com" );
+		assert_eq!( encode_html( "page#frag:ment", HtmlContext::Url ),					"page#frag:ment" );
+		assert_eq!( encode_html( "JavaScript:alert(1)", HtmlContext::Url ),				"about:invalid" );
+		assert_eq!( encode_html( " java\tscript:alert(1)", HtmlContext::Url ),			"about:invalid" );
+		assert_eq!( encode_html( "data:text/html,<b>", HtmlContext::Url ),				"about:invalid" );
+	}
+}
@@ -0,0 +1,210 @@
+// JSON string escapement, for when all we have is the inside of a string
+// See: The JavaScript Object Notation (JSON) Data Interchange Format https://tools.ietf.org/html/rfc8259#section-7
+
+use alloc::borrow::Cow;
+use alloc::fmt::Write;
+use alloc::string::String;
+
+use Replacement;
+
+/// How much of a string `encode_json_string` escapes
+#[derive( Clone, Copy, Debug, PartialEq, Eq )]
+pub enum JsonEscape {
+	/// Only what JSON requires: `"`, `\` and the C0 control characters
+	Minimal,
+	/// As `Minimal`, and every non-ASCII character as `\uXXXX`, using surrogate pairs past the BMP
+	Ascii,
+}
+
+/// Decodes the escapes found inside a JSON string into their UTF-8 form
+///
+/// The surrounding quotes are not expected, so this works on fragments lifted
+/// from log lines as well as on whole values. Lone UTF-16 surrogates become
+/// U+FFFD; see `decode_json_string_with` to choose otherwise. Escapes JSON
+/// does not define, such as `\x`, are passed through unchanged.
+///
+/// # Examples
+///
+/// Can be called with `&' str`
+///
+/// ```
+/// use mung::decode_json_string;
+/// let message = decode_json_string( r#"she said \"hi\" \ud83d\ude00"# );
+/// ```
+pub fn decode_json_string<'a>( s: &'a str ) -> Cow<'a, str> {
+	decode_json_string_with( s, Replacement::Replace )
+}
+
+/// Decodes the escapes found inside a JSON string, handling lone surrogates with `replacement`
+///
+/// # Examples
+///
+/// ```
+/// use mung::{decode_json_string_with, Replacement};
+/// let broken = decode_json_string_with( r"\ud83d", Replacement::Preserve );
+/// assert_eq!( broken, r"\ud83d" );
+/// ```
+pub fn decode_json_string_with<'a>( s: &'a str, replacement: Replacement ) -> Cow<'a, str> {
+
+	if !s.contains( '\\' ) {
+		return s.into( );
+	}
+
+	let mut allo = String::with_capacity( s.len( ) );
+	let mut rest = s;
+
+	while let Some( found ) = rest.find( '\\' ) {
+		allo.push_str( &rest[ ..found ] );
+		rest = &rest[ found..];
+
+		match escape( rest ) {
+			Some( ( length, Some( c ) ) ) => {
+				allo.push( c );
+				rest = &rest[ length..];
+			},
+			Some( ( length, None ) ) => {
+				replacement.push_to( &mut allo, &rest[ ..length ] );
+				rest = &rest[ length..];
+			},
+			None => {
+				// Not an escape JSON knows; keep the backslash and carry on
+				allo.push( '\\' );
+				rest = &rest[ 1..];
+			}
+		}
+	}
+	allo.push_str( rest );
+
+	allo.into( )
+}
+
+/// Reads the escape that starts `s`, with its length and the character it stands for
+///
+/// A surrogate pair is one escape; a lone surrogate stands for no character.
+pub(crate) fn escape( s: &str ) -> Option<( usize, Option<char> )> {
+	let simple = match s.as_bytes( ).get( 1 ) {
+		Some( &b'"' )	=> '"',
+		Some( &b'\\' )	=> '\\',
+		Some( &b'/' )	=> '/',
+		Some( &b'b' )	=> '\u{8}',
+		Some( &b'f' )	=> '\u{C}',
+		Some( &b'n' )	=> '\n',
+		Some( &b'r' )	=> '\r',
+		Some( &b't' )	=> '\t',
+		_ => {
+			let unit = utf16_escape( s )?;
+			return Some( match unit {
+				0xD800 ..= 0xDBFF => match utf16_escape( &s[ 6..] ) {
+					Some( low @ 0xDC00 ..= 0xDFFF ) => ( 12, char::from_u32( 0x10000 + ( ( unit - 0xD800 ) << 10 ) + ( low - 0xDC00 ) ) ),
+					_ => ( 6, None ),
+				},
+				_ => ( 6, char::from_u32( unit ) ),
+			} );
+		}
+	};
+	Some( ( 2, Some( simple ) ) )
+}
+
+/// Reads a `\uXXXX` escape from the start of `s`, if there is one
+fn utf16_escape( s: &str ) -> Option<u32> {
+	let bytes = s.as_bytes( );
+	if bytes.len( ) < 6 || bytes[ 0 ] != b'\\' || bytes[ 1 ] != b'u' {
+		return None;
+	}
+	if !bytes[ 2..6 ].iter( ).all( |b| b.is_ascii_hexdigit( ) ) {
+		return None;
+	}
+	u32::from_str_radix( &s[ 2..6 ], 16 ).ok( )
+}
+
+/// Encodes text so it can be placed between the quotes of a JSON string
+///
+/// # Examples
+///
+/// ```
+/// use mung::{encode_json_string, JsonEscape};
+/// assert_eq!( encode_json_string( "tab\there", JsonEscape::Minimal ), r"tab\there" );
+/// assert_eq!( encode_json_string( "café", JsonEscape::Ascii ), r"caf\u00e9" );
+/// ```
+pub fn encode_json_string<'a>( s: &'a str, mode: JsonEscape ) -> Cow<'a, str> {
+
+	let needs_escape = |c: char| {
+		c == '"' || c == '\\' || c < ' ' || ( mode == JsonEscape::Ascii && !c.is_ascii( ) )
+	};
+
+	if !s.chars( ).any( needs_escape ) {
+		return s.into( );
+	}
+
+	let mut allo = String::with_capacity( s.len( ) + 8 );
+	for c in s.chars( ) {
+		match c {
+			'"'			=> allo.push_str( "\\\"" ),
+			'\\'		=> allo.push_str( "\\\\" ),
+			'\u{8}'		=> allo.push_str( "\\b" ),
+			'\u{C}'		=> allo.push_str( "\\f" ),
+			'\n'		=> allo.push_str( "\\n" ),
+			'\r'		=> allo.push_str( "\\r" ),
+			'\t'		=> allo.push_str( "\\t" ),
+			c if needs_escape( c ) => {
+				let mut units = [ 0u16; 2 ];
+				for unit in c.encode_utf16( &mut units ) {
+					let _ = write!( allo, "\\u{:04x}", unit );
+				}
+			},
+			c => allo.push( c ),
+		}
+	}
+
+	allo.into( )
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_decode_json_string( ) {
+
+		assert_eq!( decode_json_string( "" ),						"" );
+		assert_eq!( decode_json_string( "plain" ),					"plain" );
+		assert_eq!( decode_json_string( r#"\"quoted\""# ),			"\"quoted\"" );
+		assert_eq!( decode_json_string( r"a\/b\\c" ),				"a/b\\c" );
+		assert_eq!( decode_json_string( r"\b\f\n\r\t" ),			"\u{8}\u{C}\n\r\t" );
+		assert_eq!( decode_json_string( r"caf\u00e9" ),				"café" );
+		assert_eq!( decode_json_string( r"\u2665\u2665" ),			"♥♥" );
+		assert_eq!( decode_json_string( r"\ud83d\ude00" ),			"😀" );
+		assert_eq!( decode_json_string( r"\uD83D\uDE00!" ),			"😀!" );
+
+		// Not JSON, left as found
+		assert_eq!( decode_json_string( r"\x41 \u12 \" ),			r"\x41 \u12 \" );
+	}
+
+	#[test]
+	fn test_decode_json_string_surrogates( ) {
+
+		assert_eq!( decode_json_string( r"\ud83d" ),				"�" );
+		assert_eq!( decode_json_string( r"\ude00\ud83d" ),			"��" );
+		assert_eq!( decode_json_string( r"\ud83dx" ),				"�x" );
+		assert_eq!( decode_json_string( r"\ud83d\u0041" ),			"�A" );
+		assert_eq!( decode_json_string_with( r"a\ud83db", Replacement::Preserve ),	r"a\ud83db" );
+		assert_eq!( decode_json_string_with( r"a\ud83db", Replacement::Remove ),	"ab" );
+		assert_eq!( decode_json_string_with( r"\ud83d\ude00", Replacement::Remove ),	"😀" );
+	}
+
+	#[test]
+	fn test_encode_json_string( ) {
+
+		assert_eq!( encode_json_string( "plain", JsonEscape::Minimal ),			"plain" );
+		assert_eq!( encode_json_string( "say \"hi\"", JsonEscape::Minimal ),	r#"say \"hi\""# );
+		assert_eq!( encode_json_string( "a\\b/c", JsonEscape::Minimal ),		r"a\\b/c" );
+		assert_eq!( encode_json_string( "\u{1}\n", JsonEscape::Minimal ),		r"\u0001\n" );
+		assert_eq!( encode_json_string( "café 😀", JsonEscape::Minimal ),		"café 😀" );
+		assert_eq!( encode_json_string( "café 😀", JsonEscape::Ascii ),			r"caf\u00e9 \ud83d\ude00" );
+
+		let original = "\"tricky\"\t\\ ♥ 😀 \u{7f}";
+		assert_eq!( decode_json_string( &encode_json_string( original, JsonEscape::Minimal ) ), original );
+		assert_eq!( decode_json_string( &encode_json_string( original, JsonEscape::Ascii ) ), original );
+	}
+}
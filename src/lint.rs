@@ -0,0 +1,169 @@
+// Checking hand-written HTML for escapes that are missing, mistyped or doubled
+
+use alloc::fmt;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use token::{entity_tokens, Token};
+use {escape_length, suggest_entity, EntitySet};
+
+/// The kinds of problem `lint_entities` reports
+#[derive( Clone, Copy, Debug, PartialEq, Eq )]
+pub enum Lint {
+	/// An `&` that starts no reference, and so should be written `&amp;`
+	BareAmpersand,
+	/// A well-formed reference to a name the set does not know, such as `&fred;`;
+	/// the fix is the closest known name where there is one, as `&hearts;` for `&heart;`
+	UnknownEntity,
+	/// A reference that a browser would guess at, as it lacks its `;`
+	MissingSemicolon,
+	/// A numeric reference to a codepoint that cannot be a character, or to NUL
+	InvalidCodepoint,
+	/// A reference whose `&` was escaped again, such as `&amp;amp;`
+	DoubleEscaped,
+}
+
+/// One problem found by `lint_entities`, where it is, and what to write instead
+#[derive( Clone, Debug, PartialEq, Eq )]
+pub struct Diagnostic {
+	pub lint: Lint,
+	/// Byte offsets of the problem in the text that was checked
+	pub span: Range<usize>,
+	/// The text that should replace the span
+	pub fix: String,
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result {
+		if self.lint == Lint::UnknownEntity && !self.fix.starts_with( "&amp;" ) {
+			return write!( f, "unknown entity at {}..{}, did you mean {:?}?", self.span.start, self.span.end, self.fix );
+		}
+		let problem = match self.lint {
+			Lint::BareAmpersand => "bare &",
+			Lint::UnknownEntity => "unknown entity",
+			Lint::MissingSemicolon => "reference without ;",
+			Lint::InvalidCodepoint => "reference to an invalid codepoint",
+			Lint::DoubleEscaped => "reference escaped twice",
+		};
+		write!( f, "{} at {}..{}, write {:?}", problem, self.span.start, self.span.end, self.fix )
+	}
+}
+
+/// Checks the entities of HTML or XML text, as `decode_entities_with` would read them
+///
+/// Reports bare `&`s, unknown or misspelled names, references missing their `;`, numeric
+/// references that can be no character, and doubled escapes like `&amp;lt;`.
+/// Each comes with the text to put in its place.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{lint_entities, EntitySet, Lint};
+/// let found = lint_entities( "Fish & Chips &amp;amp; &copy 2024 &#xD800;", EntitySet::Html4 );
+/// let lints: Vec<Lint> = found.iter( ).map( |diagnostic| diagnostic.lint ).collect( );
+/// assert_eq!( lints, vec![ Lint::BareAmpersand, Lint::DoubleEscaped, Lint::MissingSemicolon, Lint::InvalidCodepoint ] );
+/// assert_eq!( found[ 1 ].span, 13..22 );
+/// assert_eq!( found[ 1 ].fix, "&amp;" );
+/// ```
+pub fn lint_entities( s: &str, set: EntitySet ) -> Vec<Diagnostic> {
+
+	let mut found = Vec::new( );
+	let mut at = 0;
+
+	for token in entity_tokens( s, set ) {
+		at = match token {
+			Token::Literal( text ) => {
+				lint_literal( text, at, set, &mut found );
+				at + text.len( )
+			},
+			Token::NamedEntity { name, span, .. } => {
+				// An &amp; before what would itself be a well-formed escape was probably escaped before;
+				// not before any word and ;, as in Q&amp;A; which was meant
+				if let Some( length ) = escape_length( &s[ span.end..], set ).filter( |_| name == "amp" ) {
+					let fix = format!( "&{}", &s[ span.end..span.end + length ] );
+					found.push( Diagnostic { lint: Lint::DoubleEscaped, span: span.start..span.end + length, fix } );
+				}
+				span.end
+			},
+			Token::UnknownEntity { name, span } => {
+				let fix = match suggest_entity( name, set ) {
+					Some( known ) => format!( "&{};", known ),
+					None => format!( "&amp;{}", &s[ span.start + 1..span.end ] ),
+				};
+				found.push( Diagnostic { lint: Lint::UnknownEntity, span: span.clone( ), fix } );
+				span.end
+			},
+			Token::NumericRef { codepoint, span } => {
+				if codepoint == 0 || char::from_u32( codepoint ).is_none( ) {
+					found.push( Diagnostic { lint: Lint::InvalidCodepoint, span: span.clone( ), fix: "&#xFFFD;".into( ) } );
+				}
+				span.end
+			},
+			_ => at,
+		};
+	}
+
+	found
+}
+
+/// Looks for `&`s in literal text, found `at` in the whole, that start no reference
+fn lint_literal( text: &str, at: usize, set: EntitySet, found: &mut Vec<Diagnostic> ) {
+	for ( offset, _ ) in text.match_indices( '&' ) {
+		let body = &text[ offset + 1..];
+		let numeric = body.starts_with( '#' );
+		let skip = if body.starts_with( "#x" ) { 2 } else if numeric { 1 } else { 0 };
+		let length = body[ skip..].bytes( ).take_while( |byte| if numeric && skip == 2 { byte.is_ascii_hexdigit( ) } else if numeric { byte.is_ascii_digit( ) } else { byte.is_ascii_alphanumeric( ) } ).count( );
+		let name = &body[ ..skip + length ];
+		let start = at + offset;
+
+		if length > 0 && ( numeric || set.get( name ).is_some( ) ) {
+			found.push( Diagnostic { lint: Lint::MissingSemicolon, span: start..start + 1 + name.len( ), fix: format!( "&{};", name ) } );
+		} else {
+			found.push( Diagnostic { lint: Lint::BareAmpersand, span: start..start + 1, fix: "&amp;".into( ) } );
+		}
+	}
+}
+
+
+#[cfg( test )]
+mod tests {
+
+	use super::*;
+
+	fn lints( s: &str ) -> Vec<( Lint, Range<usize>, String )> {
+		lint_entities( s, EntitySet::Xhtml1 ).into_iter( ).map( |found| ( found.lint, found.span, found.fix ) ).collect( )
+	}
+
+	#[test]
+	fn test_lint_entities( ) {
+		assert_eq!( lints( "Fish &amp; Chips &hearts; &#9829;" ),	vec![ ] );
+		assert_eq!( lints( "AT&T" ),			vec![ ( Lint::BareAmpersand, 2..3, "&amp;".into( ) ) ] );
+		assert_eq!( lints( "&fred;" ),			vec![ ( Lint::UnknownEntity, 0..6, "&amp;fred;".into( ) ) ] );
+		assert_eq!( lints( "&lt &#38 &#x26" ),	vec![
+			( Lint::MissingSemicolon, 0..3, "&lt;".into( ) ),
+			( Lint::MissingSemicolon, 4..8, "&#38;".into( ) ),
+			( Lint::MissingSemicolon, 9..14, "&#x26;".into( ) ),
+		] );
+		assert_eq!( lints( "&#0;&#1114112;" ),	vec![
+			( Lint::InvalidCodepoint, 0..4, "&#xFFFD;".into( ) ),
+			( Lint::InvalidCodepoint, 4..14, "&#xFFFD;".into( ) ),
+		] );
+		assert_eq!( lints( "x&amp;lt;y&amp;#38;" ),	vec![
+			( Lint::DoubleEscaped, 1..9, "&lt;".into( ) ),
+			( Lint::DoubleEscaped, 10..19, "&#38;".into( ) ),
+		] );
+		assert_eq!( lints( "&amp;& &" ),	vec![
+			( Lint::BareAmpersand, 5..6, "&amp;".into( ) ),
+			( Lint::BareAmpersand, 7..8, "&amp;".into( ) ),
+		] );
+		assert_eq!( lints( "Q&amp;A; session, AT&amp;T; R&amp;D;" ),	vec![ ] );
+		assert_eq!( lints( "&amp;fred; &amp;#0;" ),	vec![ ] );
+		assert_eq!( lints( "&hellp; &heart;" ),	vec![
+			( Lint::UnknownEntity, 0..7, "&hellip;".into( ) ),
+			( Lint::UnknownEntity, 8..15, "&hearts;".into( ) ),
+		] );
+		assert_eq!( lint_entities( "&heart;", EntitySet::Html5 )[ 0 ].to_string( ),	"unknown entity at 0..7, did you mean \"&hearts;\"?" );
+		assert_eq!( lint_entities( "&fred;", EntitySet::Html5 )[ 0 ].to_string( ),	"unknown entity at 0..6, write \"&amp;fred;\"" );
+	}
+}
@@ -0,0 +1,547 @@
+// Backslash escapement as written by programming languages, for strings lifted
+// out of printf output, Rust’s {:?} and Python’s repr()
+
+use alloc::borrow::Cow;
+use alloc::fmt::Write;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use unicode_names::{NAME_ALIASES, NUMBERED_NAMES};
+#[cfg( feature = "unicode-names" )]
+use unicode_names::UNICODE_NAMES;
+use Replacement;
+
+/// The language whose string literal rules to follow
+#[derive( Clone, Copy, Debug, PartialEq, Eq )]
+pub enum Dialect {
+	/// C11: `\n`, `\x7f`, `\177`, `\u00e9`, `\U0001F600`; octal and `\x` escapes are bytes of UTF-8
+	C,
+	/// Rust: `\n`, `\0`, `\x7f`, `\u{1F600}`, as emitted by `{:?}` and `escape_debug`
+	Rust,
+	/// Python 3 `str`: `\n`, `\x7f`, `\177`, `\u00e9`, `\U0001F600`, `\N{EM DASH}`; every escape is a codepoint
+	Python,
+}
+
+/// What one backslash sequence turned out to be
+pub(crate) enum Escape {
+	Char( char ),
+	Byte( u8 ),
+	// a well formed escape for a codepoint that cannot exist
+	Invalid,
+	// a backslash before a newline, which joins lines
+	Continuation,
+	// not an escape in this dialect, keep the backslash as text
+	Unknown,
+}
+
+/// Decodes backslash escapes in the style of `dialect` into their UTF-8 form
+///
+/// Escapes that name an impossible character, such as `\U00110000`, become
+/// U+FFFD, as do C byte escapes that are not valid UTF-8; see
+/// `decode_literal_with` to choose otherwise. Escapes the dialect does not
+/// know are passed through unchanged.
+///
+/// # Examples
+///
+/// Can be called with `&' str`
+///
+/// ```
+/// use mung::{decode_literal, Dialect};
+/// let message = decode_literal( r"caf\303\251\tok\n", Dialect::C );
+/// ```
+///
+/// or with what Rust’s `escape_debug` produced
+///
+/// ```
+/// use mung::{decode_literal, Dialect};
+/// let original = "tab\there ♥\u{0}";
+/// let debugged = original.escape_debug( ).to_string( );
+/// assert_eq!( decode_literal( &debugged, Dialect::Rust ), original );
+/// ```
+pub fn decode_literal<'a>( s: &'a str, dialect: Dialect ) -> Cow<'a, str> {
+	decode_literal_with( s, dialect, Replacement::Replace )
+}
+
+/// Decodes backslash escapes in the style of `dialect`, handling impossible characters with `replacement`
+pub fn decode_literal_with<'a>( s: &'a str, dialect: Dialect, replacement: Replacement ) -> Cow<'a, str> {
+
+	if !s.contains( '\\' ) {
+		return s.into( );
+	}
+
+	let mut allo = String::with_capacity( s.len( ) );
+	// C byte escapes have to be gathered up before they can be read as UTF-8
+	let mut pending: Vec<( u8, &'a str )> = Vec::new( );
+	let mut rest = s;
+
+	while let Some( found ) = rest.find( '\\' ) {
+		if found > 0 {
+			flush_bytes( &mut allo, &mut pending, replacement );
+			allo.push_str( &rest[ ..found ] );
+		}
+		rest = &rest[ found..];
+
+		let ( mut length, escape ) = escape( rest, dialect );
+
+		match escape {
+			Escape::Byte( byte ) => {
+				pending.push( ( byte, &rest[ ..length ] ) );
+			},
+			Escape::Char( c ) => {
+				flush_bytes( &mut allo, &mut pending, replacement );
+				allo.push( c );
+			},
+			Escape::Invalid => {
+				flush_bytes( &mut allo, &mut pending, replacement );
+				replacement.push_to( &mut allo, &rest[ ..length ] );
+			},
+			Escape::Continuation => {
+				flush_bytes( &mut allo, &mut pending, replacement );
+			},
+			Escape::Unknown => {
+				flush_bytes( &mut allo, &mut pending, replacement );
+				allo.push( '\\' );
+				length = 1;
+			},
+		}
+		rest = &rest[ length..];
+	}
+	flush_bytes( &mut allo, &mut pending, replacement );
+	allo.push_str( rest );
+
+	allo.into( )
+}
+
+/// Writes out gathered byte escapes, as UTF-8 where they form it
+fn flush_bytes( allo: &mut String, pending: &mut Vec<( u8, &str )>, replacement: Replacement ) {
+
+	if pending.is_empty( ) {
+		return;
+	}
+
+	let raw: Vec<u8> = pending.iter( ).map( |&( byte, _ )| byte ).collect( );
+	let mut start = 0;
+
+	while start < raw.len( ) {
+		match ::alloc::str::from_utf8( &raw[ start..] ) {
+			Ok( valid ) => {
+				allo.push_str( valid );
+				break;
+			},
+			Err( error ) => {
+				let good = start + error.valid_up_to( );
+				allo.push_str( ::alloc::str::from_utf8( &raw[ start..good ] ).unwrap_or( "" ) );
+
+				let bad = error.error_len( ).unwrap_or( raw.len( ) - good );
+				let original: String = pending[ good..good + bad ].iter( ).map( |&( _, text )| text ).collect( );
+				replacement.push_to( allo, &original );
+				start = good + bad;
+			}
+		}
+	}
+	pending.clear( );
+}
+
+/// Counts how many digits of `radix` begin `s`, up to `most`, and returns their value
+fn digits( s: &str, radix: u32, most: usize ) -> ( usize, u32 ) {
+	let length = s.bytes( ).take( most ).take_while( |b| ( *b as char ).is_digit( radix ) ).count( );
+	if length == 0 {
+		return ( 0, 0 );
+	}
+	// saturate rather than overflow on long \x runs
+	let value = u32::from_str_radix( &s[ ..length ], radix ).unwrap_or( u32::MAX );
+	( length, value )
+}
+
+/// Reads exactly `count` hex digits after a two character introducer such as `\u`
+fn fixed_codepoint( s: &str, count: usize ) -> ( usize, Escape ) {
+	let ( length, value ) = digits( &s[ 2..], 16, count );
+	if length != count {
+		return ( 1, Escape::Unknown );
+	}
+	match char::from_u32( value ) {
+		Some( c ) => ( 2 + count, Escape::Char( c ) ),
+		None => ( 2 + count, Escape::Invalid ),
+	}
+}
+
+/// A backslash before a line ending joins the lines
+fn continuation( s: &str ) -> Option<usize> {
+	if s[ 1..].starts_with( "\r\n" ) {
+		Some( 3 )
+	} else if s[ 1..].starts_with( '\n' ) {
+		Some( 2 )
+	} else {
+		None
+	}
+}
+
+/// Reads the backslash sequence that starts `s`, with its length
+pub(crate) fn escape( s: &str, dialect: Dialect ) -> ( usize, Escape ) {
+	match dialect {
+		Dialect::C		=> c_escape( s ),
+		Dialect::Rust	=> rust_escape( s ),
+		Dialect::Python	=> python_escape( s ),
+	}
+}
+
+fn c_escape( s: &str ) -> ( usize, Escape ) {
+
+	if let Some( length ) = continuation( s ) {
+		return ( length, Escape::Continuation );
+	}
+
+	let simple = match s.as_bytes( ).get( 1 ) {
+		Some( &b'a' )	=> '\u{7}',
+		Some( &b'b' )	=> '\u{8}',
+		Some( &b'f' )	=> '\u{C}',
+		Some( &b'n' )	=> '\n',
+		Some( &b'r' )	=> '\r',
+		Some( &b't' )	=> '\t',
+		Some( &b'v' )	=> '\u{B}',
+		Some( &b'\\' )	=> '\\',
+		Some( &b'\'' )	=> '\'',
+		Some( &b'"' )	=> '"',
+		Some( &b'?' )	=> '?',
+		Some( &( b'0' ..= b'7' ) ) => {
+			let ( length, value ) = digits( &s[ 1..], 8, 3 );
+			return if value <= 0xFF {
+				( 1 + length, Escape::Byte( value as u8 ) )
+			} else {
+				( 1 + length, Escape::Invalid )
+			};
+		},
+		Some( &b'x' ) => {
+			// C reads every hex digit that follows, however many there are
+			let ( length, value ) = digits( &s[ 2..], 16, usize::MAX );
+			return match length {
+				0 => ( 1, Escape::Unknown ),
+				_ if value <= 0xFF => ( 2 + length, Escape::Byte( value as u8 ) ),
+				_ => ( 2 + length, Escape::Invalid ),
+			};
+		},
+		Some( &b'u' ) => return fixed_codepoint( s, 4 ),
+		Some( &b'U' ) => return fixed_codepoint( s, 8 ),
+		_ => return ( 1, Escape::Unknown ),
+	};
+
+	( 2, Escape::Char( simple ) )
+}
+
+fn rust_escape( s: &str ) -> ( usize, Escape ) {
+
+	if let Some( length ) = continuation( s ) {
+		// Rust also swallows the indentation of the following line
+		let indent = s[ length..].len( ) - s[ length..].trim_start( ).len( );
+		return ( length + indent, Escape::Continuation );
+	}
+
+	let simple = match s.as_bytes( ).get( 1 ) {
+		Some( &b'n' )	=> '\n',
+		Some( &b'r' )	=> '\r',
+		Some( &b't' )	=> '\t',
+		Some( &b'0' )	=> '\0',
+		Some( &b'\\' )	=> '\\',
+		Some( &b'\'' )	=> '\'',
+		Some( &b'"' )	=> '"',
+		Some( &b'x' ) => {
+			let ( length, value ) = digits( &s[ 2..], 16, 2 );
+			return match length {
+				2 if value <= 0x7F => ( 4, Escape::Char( value as u8 as char ) ),
+				2 => ( 4, Escape::Invalid ),
+				_ => ( 1, Escape::Unknown ),
+			};
+		},
+		Some( &b'u' ) => {
+			if !s[ 2..].starts_with( '{' ) {
+				return ( 1, Escape::Unknown );
+			}
+			let ( length, value ) = digits( &s[ 3..], 16, 6 );
+			if length == 0 || !s[ 3 + length..].starts_with( '}' ) {
+				return ( 1, Escape::Unknown );
+			}
+			return match char::from_u32( value ) {
+				Some( c ) => ( 4 + length, Escape::Char( c ) ),
+				None => ( 4 + length, Escape::Invalid ),
+			};
+		},
+		_ => return ( 1, Escape::Unknown ),
+	};
+
+	( 2, Escape::Char( simple ) )
+}
+
+fn python_escape( s: &str ) -> ( usize, Escape ) {
+
+	if let Some( length ) = continuation( s ) {
+		return ( length, Escape::Continuation );
+	}
+
+	let simple = match s.as_bytes( ).get( 1 ) {
+		Some( &b'a' )	=> '\u{7}',
+		Some( &b'b' )	=> '\u{8}',
+		Some( &b'f' )	=> '\u{C}',
+		Some( &b'n' )	=> '\n',
+		Some( &b'r' )	=> '\r',
+		Some( &b't' )	=> '\t',
+		Some( &b'v' )	=> '\u{B}',
+		Some( &b'\\' )	=> '\\',
+		Some( &b'\'' )	=> '\'',
+		Some( &b'"' )	=> '"',
+		Some( &( b'0' ..= b'7' ) ) => {
+			let ( length, value ) = digits( &s[ 1..], 8, 3 );
+			return match char::from_u32( value ) {
+				Some( c ) => ( 1 + length, Escape::Char( c ) ),
+				None => ( 1 + length, Escape::Invalid ),
+			};
+		},
+		Some( &b'x' ) => return fixed_codepoint( s, 2 ),
+		Some( &b'u' ) => return fixed_codepoint( s, 4 ),
+		Some( &b'U' ) => return fixed_codepoint( s, 8 ),
+		Some( &b'N' ) => {
+			if !s[ 2..].starts_with( '{' ) {
+				return ( 1, Escape::Unknown );
+			}
+			return match s[ 3..].find( '}' ) {
+				Some( end ) => match unicode_name( &s[ 3..3 + end ] ) {
+					Some( c ) => ( 4 + end, Escape::Char( c ) ),
+					None => ( 4 + end, Escape::Invalid ),
+				},
+				None => ( 1, Escape::Unknown ),
+			};
+		},
+		_ => return ( 1, Escape::Unknown ),
+	};
+
+	( 2, Escape::Char( simple ) )
+}
+
+/// The jamo of a Hangul syllable's name: its leading consonant, vowel and trailing consonant
+static HANGUL_LEADS: &[&str] = &[ "G", "GG", "N", "D", "DD", "R", "M", "B", "BB", "S", "SS", "", "J", "JJ", "C", "K", "T", "P", "H" ];
+static HANGUL_VOWELS: &[&str] = &[ "A", "AE", "YA", "YAE", "EO", "E", "YEO", "YE", "O", "WA", "WAE", "OE", "YO", "U", "WEO", "WE", "WI", "YU", "EU", "YI", "I" ];
+static HANGUL_TAILS: &[&str] = &[ "", "G", "GG", "GS", "N", "NJ", "NH", "D", "L", "LG", "LM", "LB", "LS", "LT", "LP", "LH", "M", "B", "BS", "S", "SS", "NG", "J", "C", "K", "T", "P", "H" ];
+
+/// Looks up a Unicode character name, or one of its aliases, ignoring case as Python does
+///
+/// Without the `unicode-names` feature, only aliases and the names made by rule are known.
+fn unicode_name( name: &str ) -> Option<char> {
+
+	let name = name.to_uppercase( );
+
+	#[cfg( feature = "unicode-names" )]
+	let tables = [ UNICODE_NAMES, NAME_ALIASES ];
+	#[cfg( not( feature = "unicode-names" ) )]
+	let tables = [ NAME_ALIASES ];
+	for table in &tables {
+		if let Ok( found ) = table.binary_search_by( |&( known, _ )| known.cmp( &name ) ) {
+			return Some( table[ found ].1 );
+		}
+	}
+
+	// The rest are worked out, as the Unicode Standard names them by rule
+	if let Some( syllable ) = name.strip_prefix( "HANGUL SYLLABLE " ) {
+		return hangul_syllable( syllable );
+	}
+	for &( prefix, first, last ) in NUMBERED_NAMES {
+		if let Some( hex ) = name.strip_prefix( prefix ) {
+			if hex.len( ) >= 4 && hex.bytes( ).all( |byte| byte.is_ascii_hexdigit( ) ) {
+				let codepoint = u32::from_str_radix( hex, 16 ).ok( )?;
+				if first <= codepoint && codepoint <= last {
+					return char::from_u32( codepoint );
+				}
+			}
+		}
+	}
+
+	None
+}
+
+/// The Hangul syllable whose name, after `HANGUL SYLLABLE `, is `syllable`
+fn hangul_syllable( syllable: &str ) -> Option<char> {
+	for ( lead, &lead_name ) in HANGUL_LEADS.iter( ).enumerate( ) {
+		let Some( rest ) = syllable.strip_prefix( lead_name ) else { continue };
+		for ( vowel, &vowel_name ) in HANGUL_VOWELS.iter( ).enumerate( ) {
+			let Some( tail_name ) = rest.strip_prefix( vowel_name ) else { continue };
+			if let Some( tail ) = HANGUL_TAILS.iter( ).position( |&known| known == tail_name ) {
+				return char::from_u32( 0xAC00 + ( lead * HANGUL_VOWELS.len( ) + vowel ) as u32 * HANGUL_TAILS.len( ) as u32 + tail as u32 );
+			}
+		}
+	}
+	None
+}
+
+/// Encodes text as the inside of a string literal in the style of `dialect`
+///
+/// C output escapes everything outside printable ASCII as octal bytes of
+/// UTF-8, so it survives any compiler. Rust output is exactly
+/// `str::escape_debug`. Python output follows `repr()` for a single quoted
+/// `str`, keeping printable characters as they are.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{encode_literal, Dialect};
+/// assert_eq!( encode_literal( "café\n", Dialect::C ), r"caf\303\251\n" );
+/// assert_eq!( encode_literal( "it's\0", Dialect::Python ), r"it\'s\x00" );
+/// ```
+pub fn encode_literal<'a>( s: &'a str, dialect: Dialect ) -> Cow<'a, str> {
+
+	let needs_escape = |c: char| match dialect {
+		Dialect::C		=> c == '\\' || c == '"' || !( ' ' ..= '~' ).contains( &c ),
+		Dialect::Rust	=> c.escape_debug( ).len( ) > 1,
+		Dialect::Python	=> c == '\\' || c == '\'' || python_unprintable( c ),
+	};
+
+	if !s.chars( ).any( needs_escape ) {
+		return s.into( );
+	}
+
+	if dialect == Dialect::Rust {
+		return s.escape_debug( ).to_string( ).into( );
+	}
+
+	let mut allo = String::with_capacity( s.len( ) + 8 );
+	for c in s.chars( ) {
+		match c {
+			'\\'	=> allo.push_str( "\\\\" ),
+			'\n'	=> allo.push_str( "\\n" ),
+			'\r'	=> allo.push_str( "\\r" ),
+			'\t'	=> allo.push_str( "\\t" ),
+			c if !needs_escape( c ) => allo.push( c ),
+			c => match dialect {
+				Dialect::C => match c {
+					'"'			=> allo.push_str( "\\\"" ),
+					'\u{7}'		=> allo.push_str( "\\a" ),
+					'\u{8}'		=> allo.push_str( "\\b" ),
+					'\u{B}'		=> allo.push_str( "\\v" ),
+					'\u{C}'		=> allo.push_str( "\\f" ),
+					c => {
+						// octal never runs on into a following digit the way \x does
+						let mut bytes = [ 0u8; 4 ];
+						for byte in c.encode_utf8( &mut bytes ).bytes( ) {
+							let _ = write!( allo, "\\{:03o}", byte );
+						}
+					}
+				},
+				_ => match c {
+					'\'' => allo.push_str( "\\'" ),
+					c if ( c as u32 ) < 0x100 => { let _ = write!( allo, "\\x{:02x}", c as u32 ); },
+					c if ( c as u32 ) < 0x10000 => { let _ = write!( allo, "\\u{:04x}", c as u32 ); },
+					c => { let _ = write!( allo, "\\U{:08x}", c as u32 ); },
+				},
+			},
+		}
+	}
+
+	allo.into( )
+}
+
+/// Roughly what Python’s `str.isprintable` rejects: controls, and separators other than space
+fn python_unprintable( c: char ) -> bool {
+	c.is_control( ) || ( c.is_whitespace( ) && c != ' ' ) || c == '\u{AD}' ||
+		( '\u{200B}' ..= '\u{200F}' ).contains( &c ) || c == '\u{FEFF}'
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_decode_literal_c( ) {
+
+		assert_eq!( decode_literal( "", Dialect::C ),							"" );
+		assert_eq!( decode_literal( "plain", Dialect::C ),						"plain" );
+		assert_eq!( decode_literal( r#"\a\b\f\n\r\t\v\\\'\"\?"#, Dialect::C ),	"\u{7}\u{8}\u{C}\n\r\t\u{B}\\'\"?" );
+		assert_eq!( decode_literal( r"\0", Dialect::C ),						"\0" );
+		assert_eq!( decode_literal( r"\101\102", Dialect::C ),					"AB" );
+		assert_eq!( decode_literal( r"\x7f", Dialect::C ),						"\u{7F}" );
+		assert_eq!( decode_literal( r"caf\303\251", Dialect::C ),				"café" );
+		assert_eq!( decode_literal( r"it\xe2\x80\x99s", Dialect::C ),			"it’s" );
+		assert_eq!( decode_literal( r"caf\u00e9 \U0001F600", Dialect::C ),		"café 😀" );
+		assert_eq!( decode_literal( "one \\\ntwo", Dialect::C ),				"one two" );
+
+		// Not valid UTF-8, or not a codepoint
+		assert_eq!( decode_literal( r"\xe9t\xe9", Dialect::C ),					"�t�" );
+		assert_eq!( decode_literal_with( r"\xe9t\xe9", Dialect::C, Replacement::Preserve ),	r"\xe9t\xe9" );
+		assert_eq!( decode_literal_with( r"a\xe2\x80 b", Dialect::C, Replacement::Remove ),	"a b" );
+		assert_eq!( decode_literal( r"\x100", Dialect::C ),						"�" );
+		assert_eq!( decode_literal( r"\U00110000", Dialect::C ),				"�" );
+
+		// Unknown to C, left as found
+		assert_eq!( decode_literal( r"\q \x \u12", Dialect::C ),				r"\q \x \u12" );
+	}
+
+	#[test]
+	fn test_decode_literal_rust( ) {
+
+		assert_eq!( decode_literal( r#"\n\r\t\0\\\'\""#, Dialect::Rust ),			"\n\r\t\0\\'\"" );
+		assert_eq!( decode_literal( r"\x41\x7f", Dialect::Rust ),				"A\u{7F}" );
+		assert_eq!( decode_literal( r"\u{e9}\u{1F600}", Dialect::Rust ),		"é😀" );
+		assert_eq!( decode_literal( "one \\\n    two", Dialect::Rust ),			"one two" );
+		assert_eq!( decode_literal( r"\x80", Dialect::Rust ),					"�" );
+		assert_eq!( decode_literal( r"\u{D800}", Dialect::Rust ),				"�" );
+		assert_eq!( decode_literal( r"\a \u{} A", Dialect::Rust ),			r"\a \u{} A" );
+
+		for original in &[ "plain", "tab\tline\nquote\"'", "nul\0 del\u{7F}", "♥ 😀 \u{301}e\u{301}", "\u{200B}\u{FEFF}" ] {
+			let debugged = original.escape_debug( ).to_string( );
+			assert_eq!( decode_literal( &debugged, Dialect::Rust ), *original );
+			assert_eq!( decode_literal( format!( "{:?}", original ).trim_matches( '"' ), Dialect::Rust ), *original );
+		}
+	}
+
+	#[test]
+	#[cfg( feature = "unicode-names" )]
+	fn test_decode_literal_names( ) {
+
+		assert_eq!( decode_literal( r"\N{EM DASH}\N{em dash}", Dialect::Python ),	"——" );
+		assert_eq!( decode_literal( r"\N{LATIN SMALL LETTER Q}", Dialect::Python ),	"q" );
+		assert_eq!( decode_literal( r"\N{LATIN SMALL LETTER E WITH ACUTE}", Dialect::Python ),	"é" );
+		assert_eq!( decode_literal( r"\N{Greek Small Letter Lamda}\N{snowman}", Dialect::Python ),	"λ☃" );
+	}
+
+	#[test]
+	#[cfg( not( feature = "unicode-names" ) )]
+	fn test_decode_literal_names( ) {
+
+		// Only names made by rule, and aliases, are known without the table
+		assert_eq!( decode_literal( r"\N{EM DASH}\N{CJK UNIFIED IDEOGRAPH-4E2D}\N{LF}", Dialect::Python ),	"�中\n" );
+	}
+
+	#[test]
+	fn test_decode_literal_python( ) {
+
+		assert_eq!( decode_literal( r"caf\xe9", Dialect::Python ),				"café" );
+		assert_eq!( decode_literal( r"\351", Dialect::Python ),					"é" );
+		assert_eq!( decode_literal( r"♥ \U0001f600", Dialect::Python ),	"♥ 😀" );
+		assert_eq!( decode_literal( r"\N{CJK UNIFIED IDEOGRAPH-4E2D}", Dialect::Python ),	"中" );
+		assert_eq!( decode_literal( r"\N{NUL}\N{LF}\N{BYTE ORDER MARK}", Dialect::Python ),	"\0\n\u{FEFF}" );
+		assert_eq!( decode_literal( r"\N{HANGUL SYLLABLE GAG}\N{HANGUL SYLLABLE A}\N{HANGUL SYLLABLE PWILH}", Dialect::Python ),	"\u{AC01}\u{C544}\u{D4DB}" );
+		assert_eq!( decode_literal( r"\N{TANGUT IDEOGRAPH-17000}\N{CJK UNIFIED IDEOGRAPH-4E00}", Dialect::Python ),	"\u{17000}\u{4E00}" );
+		assert_eq!( decode_literal( r"\N{CJK UNIFIED IDEOGRAPH-0041}\N{HANGUL SYLLABLE GX}", Dialect::Python ),	"��" );
+		assert_eq!( decode_literal( r"\N{NOT A NAME}", Dialect::Python ),		"�" );
+		assert_eq!( decode_literal_with( r"\N{NOT A NAME}", Dialect::Python, Replacement::Preserve ),	r"\N{NOT A NAME}" );
+		assert_eq!( decode_literal( r"\q \N \x4", Dialect::Python ),			r"\q \N \x4" );
+	}
+
+	#[test]
+	fn test_encode_literal( ) {
+
+		assert_eq!( encode_literal( "plain", Dialect::C ),						"plain" );
+		assert_eq!( encode_literal( "say \"hi\"\t\\", Dialect::C ),				r#"say \"hi\"\t\\"# );
+		assert_eq!( encode_literal( "\u{7}\u{7F}", Dialect::C ),				r"\a\177" );
+		assert_eq!( encode_literal( "it’s", Dialect::C ),						r"it\342\200\231s" );
+
+		assert_eq!( encode_literal( "plain", Dialect::Rust ),					"plain" );
+		assert_eq!( encode_literal( "nul\0 ♥", Dialect::Rust ),					r"nul\0 ♥" );
+
+		assert_eq!( encode_literal( "plain \"", Dialect::Python ),				"plain \"" );
+		assert_eq!( encode_literal( "it's ♥\u{7F}", Dialect::Python ),			r"it\'s ♥\x7f" );
+		assert_eq!( encode_literal( "\u{2028}\u{85}", Dialect::Python ),		r"\u2028\x85" );
+
+		let original = "\"tricky\"\t\\ it’s ♥ 😀 \u{7}\u{7F}";
+		for dialect in &[ Dialect::C, Dialect::Rust, Dialect::Python ] {
+			assert_eq!( decode_literal( &encode_literal( original, *dialect ), *dialect ), original );
+		}
+	}
+}
@@ -0,0 +1,286 @@
+// Punycode, and the IDNA conversion of host names that carries it
+// See: Punycode https://tools.ietf.org/html/rfc3492
+// See also: Internationalizing Domain Names in Applications https://tools.ietf.org/html/rfc3490#section-4
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use error::DecodeError;
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+/// RFC 3492 §6.1 bias adaptation
+fn adapt( delta: u32, points: u32, first: bool ) -> u32 {
+	let mut delta = if first { delta / DAMP } else { delta / 2 };
+	delta += delta / points;
+	let mut k = 0;
+	while delta > ( ( BASE - T_MIN ) * T_MAX ) / 2 {
+		delta /= BASE - T_MIN;
+		k += BASE;
+	}
+	k + ( BASE - T_MIN + 1 ) * delta / ( delta + SKEW )
+}
+
+fn threshold( k: u32, bias: u32 ) -> u32 {
+	if k <= bias {
+		T_MIN
+	} else if k >= bias + T_MAX {
+		T_MAX
+	} else {
+		k - bias
+	}
+}
+
+fn digit_value( byte: u8 ) -> Option<u32> {
+	match byte {
+		b'a' ..= b'z' => Some( u32::from( byte - b'a' ) ),
+		b'A' ..= b'Z' => Some( u32::from( byte - b'A' ) ),
+		b'0' ..= b'9' => Some( u32::from( byte - b'0' ) + 26 ),
+		_ => None
+	}
+}
+
+fn digit_symbol( value: u32 ) -> char {
+	if value < 26 {
+		( b'a' + value as u8 ) as char
+	} else {
+		( b'0' + ( value - 26 ) as u8 ) as char
+	}
+}
+
+/// Decodes a Punycode string, without any `xn--` prefix, into its UTF-8 form
+///
+/// # Examples
+///
+/// ```
+/// use mung::decode_punycode;
+/// assert_eq!( decode_punycode( "bcher-kva" ).unwrap( ), "bücher" );
+/// ```
+pub fn decode_punycode( s: &str ) -> Result<String, DecodeError> {
+
+	let bytes = s.as_bytes( );
+	if let Some( offset ) = bytes.iter( ).position( |byte| !byte.is_ascii( ) ) {
+		return Err( DecodeError::InvalidByte { offset, byte: bytes[ offset ] } );
+	}
+
+	let ( mut output, start ) = match s.rfind( '-' ) {
+		Some( delimiter ) => ( s[ ..delimiter ].chars( ).collect::<Vec<char>>( ), delimiter + 1 ),
+		None => ( Vec::new( ), 0 ),
+	};
+
+	let mut n = INITIAL_N;
+	let mut i = 0u32;
+	let mut bias = INITIAL_BIAS;
+	let mut position = start;
+
+	while position < bytes.len( ) {
+		let old_i = i;
+		let mut weight = 1u32;
+		let mut k = BASE;
+		loop {
+			let byte = match bytes.get( position ) {
+				Some( &byte ) => byte,
+				None => return Err( DecodeError::InvalidLength { length: bytes.len( ) } ),
+			};
+			let digit = digit_value( byte ).ok_or( DecodeError::InvalidByte { offset: position, byte } )?;
+			position += 1;
+
+			i = digit.checked_mul( weight ).and_then( |step| i.checked_add( step ) )
+				.ok_or( DecodeError::Overflow { offset: position - 1 } )?;
+			let t = threshold( k, bias );
+			if digit < t {
+				break;
+			}
+			weight = weight.checked_mul( BASE - t ).ok_or( DecodeError::Overflow { offset: position - 1 } )?;
+			k += BASE;
+		}
+
+		let points = output.len( ) as u32 + 1;
+		bias = adapt( i - old_i, points, old_i == 0 );
+		n = n.checked_add( i / points ).ok_or( DecodeError::Overflow { offset: position - 1 } )?;
+		i %= points;
+
+		let c = char::from_u32( n ).ok_or( DecodeError::Overflow { offset: position - 1 } )?;
+		output.insert( i as usize, c );
+		i += 1;
+	}
+
+	Ok( output.into_iter( ).collect( ) )
+}
+
+/// Encodes text as Punycode, without adding an `xn--` prefix
+///
+/// `None` is returned only for text so long that its deltas overflow, which
+/// no host name label can be.
+///
+/// # Examples
+///
+/// ```
+/// use mung::encode_punycode;
+/// assert_eq!( encode_punycode( "中国" ).unwrap( ), "fiqs8s" );
+/// ```
+pub fn encode_punycode( s: &str ) -> Option<String> {
+
+	let input: Vec<u32> = s.chars( ).map( |c| c as u32 ).collect( );
+	let mut allo: String = s.chars( ).filter( |c| c.is_ascii( ) ).collect( );
+	let basic = allo.len( ) as u32;
+	if basic > 0 {
+		allo.push( '-' );
+	}
+
+	let mut n = INITIAL_N;
+	let mut delta = 0u32;
+	let mut bias = INITIAL_BIAS;
+	let mut handled = basic;
+
+	while ( handled as usize ) < input.len( ) {
+		let m = input.iter( ).cloned( ).filter( |&c| c >= n ).min( )?;
+		delta = delta.checked_add( ( m - n ).checked_mul( handled + 1 )? )?;
+		n = m;
+
+		for &c in &input {
+			if c < n {
+				delta = delta.checked_add( 1 )?;
+			}
+			if c == n {
+				let mut q = delta;
+				let mut k = BASE;
+				loop {
+					let t = threshold( k, bias );
+					if q < t {
+						break;
+					}
+					allo.push( digit_symbol( t + ( q - t ) % ( BASE - t ) ) );
+					q = ( q - t ) / ( BASE - t );
+					k += BASE;
+				}
+				allo.push( digit_symbol( q ) );
+				bias = adapt( delta, handled + 1, handled == basic );
+				delta = 0;
+				handled += 1;
+			}
+		}
+		delta += 1;
+		n += 1;
+	}
+
+	Some( allo )
+}
+
+/// IDNA label separators: full stop, ideographic full stop, and their fullwidth and halfwidth forms
+fn is_separator( c: char ) -> bool {
+	c == '.' || c == '\u{3002}' || c == '\u{FF0E}' || c == '\u{FF61}'
+}
+
+/// Converts a domain name to its Unicode form for display, as IDNA ToUnicode does
+///
+/// Each `xn--` label is decoded from Punycode; a label that does not decode
+/// is left as it was, so the result is always something worth showing.
+///
+/// # Examples
+///
+/// ```
+/// use mung::decode_idna;
+/// assert_eq!( decode_idna( "xn--fiqs8s.cn" ), "中国.cn" );
+/// ```
+pub fn decode_idna<'a>( domain: &'a str ) -> Cow<'a, str> {
+
+	let has_ace = domain.split( '.' ).any( |label| label.len( ) > 4 && label[ ..4 ].eq_ignore_ascii_case( "xn--" ) );
+	if !has_ace {
+		return domain.into( );
+	}
+
+	let labels: Vec<Cow<str>> = domain.split( '.' ).map( |label| {
+		if label.len( ) > 4 && label[ ..4 ].eq_ignore_ascii_case( "xn--" ) {
+			match decode_punycode( &label[ 4..] ) {
+				Ok( decoded ) => decoded.into( ),
+				Err( _ ) => label.into( ),
+			}
+		} else {
+			label.into( )
+		}
+	} ).collect( );
+
+	labels.join( "." ).into( )
+}
+
+/// Converts a domain name to its ASCII form for transport, as IDNA ToASCII does
+///
+/// Labels holding anything beyond ASCII are lower cased and encoded as
+/// `xn--` Punycode, and the ideographic full stops IDNA allows become `.`.
+/// This is not the full UTS #46 mapping: no compatibility normalisation or
+/// validity checks are applied.
+///
+/// # Examples
+///
+/// ```
+/// use mung::encode_idna;
+/// assert_eq!( encode_idna( "Bücher.example" ), "xn--bcher-kva.example" );
+/// ```
+pub fn encode_idna<'a>( domain: &'a str ) -> Cow<'a, str> {
+
+	if domain.is_ascii( ) {
+		return domain.into( );
+	}
+
+	let labels: Vec<Cow<str>> = domain.split( is_separator ).map( |label| {
+		if label.is_ascii( ) {
+			return label.into( );
+		}
+		let lowered = label.to_lowercase( );
+		match encode_punycode( &lowered ) {
+			Some( encoded ) => format!( "xn--{}", encoded ).into( ),
+			None => lowered.into( ),
+		}
+	} ).collect( );
+
+	labels.join( "." ).into( )
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_punycode( ) {
+
+		for &( plain, encoded ) in &[ ( "", "" ), ( "bücher", "bcher-kva" ), ( "中国", "fiqs8s" ), ( "münchen", "mnchen-3ya" ),
+				( "ü", "tda" ), ( "😀", "e28h" ), ( "Bahnhof", "Bahnhof-" ),
+				// RFC 3492 §7.1 samples (A) and (B)
+				( "ليهمابتكلموشعربي؟", "egbpdaj6bu4bxfgehfvwxn" ), ( "他们为什么不说中文", "ihqwcrb4cv8a8dqg056pqjye" ) ] {
+			assert_eq!( encode_punycode( plain ), Some( encoded.to_string( ) ) );
+			assert_eq!( decode_punycode( encoded ), Ok( plain.to_string( ) ) );
+		}
+
+		assert_eq!( decode_punycode( "BCHER-KVA" ),		Ok( "BüCHER".to_string( ) ) );
+		assert_eq!( decode_punycode( "bcher-kv!" ),		Err( DecodeError::InvalidByte { offset: 8, byte: b'!' } ) );
+		assert_eq!( decode_punycode( "bcher-k" ),		Err( DecodeError::InvalidLength { length: 7 } ) );
+		assert!( decode_punycode( "99999999999" ).is_err( ) );
+	}
+
+	#[test]
+	fn test_idna( ) {
+
+		assert_eq!( decode_idna( "example.com" ),					"example.com" );
+		assert_eq!( decode_idna( "xn--fiqs8s.cn" ),					"中国.cn" );
+		assert_eq!( decode_idna( "www.XN--bcher-kva.example." ),	"www.bücher.example." );
+		assert_eq!( decode_idna( "xn--!!.example" ),				"xn--!!.example" );
+
+		assert_eq!( encode_idna( "example.com" ),					"example.com" );
+		assert_eq!( encode_idna( "中国.cn" ),						"xn--fiqs8s.cn" );
+		assert_eq!( encode_idna( "www.Bücher.example" ),			"www.xn--bcher-kva.example" );
+		assert_eq!( encode_idna( "中国。cn" ),						"xn--fiqs8s.cn" );
+
+		for domain in &[ "中国.cn", "münchen.de", "😀.example" ] {
+			assert_eq!( decode_idna( &encode_idna( domain ) ), *domain );
+		}
+	}
+}
@@ -0,0 +1,278 @@
+// Codecs by name, for pipelines spelt out in configuration rather than code
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use codec::{Base16, Decode, Encode, Entities, Idna, ImapUtf7, Json, Percent, QuotedPrintable, Utf7};
+#[cfg( feature = "std" )]
+use codec::Rfc2047;
+use error::UnknownCodec;
+use {Base32, Base64, Base85, DecodeError, Dialect, EncodeSet, EntitySet, HtmlContext, XmlOptions};
+
+/// Codecs looked up by name, such as `url` or `rfc2047`, and by their aliases
+///
+/// `Registry::new` knows every codec in this crate; `register_decoder` and
+/// `register_encoder` add more, or take a name over. Names are matched
+/// without regard to case, and `_` is read as `-`.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{Decode, Registry};
+/// let registry = Registry::new( );
+/// let pipeline = registry.parse_decoders( "[url, html]" ).unwrap( );
+/// assert_eq!( pipeline.decode( "Fish%20&amp;%20Chips" ).unwrap( ), "Fish & Chips" );
+/// assert!( registry.parse_decoders( "url, rot13" ).is_err( ) );
+/// ```
+///
+/// Downstream crates can add their own
+///
+/// ```
+/// use std::borrow::Cow;
+/// use mung::{Decode, DecodeError, Registry};
+///
+/// struct Shout;
+/// impl Decode for Shout {
+///     fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+///         Ok( s.to_uppercase( ).into( ) )
+///     }
+/// }
+///
+/// let mut registry = Registry::new( );
+/// registry.register_decoder( &[ "shout", "upper" ], Shout );
+/// assert_eq!( registry.parse_decoders( "html, upper" ).unwrap( ).decode( "&lt;b&gt;" ).unwrap( ), "<B>" );
+/// ```
+pub struct Registry {
+	decoders: Table<dyn Decode>,
+	encoders: Table<dyn Encode>,
+}
+
+/// The codecs of one direction, and the names that lead to them
+struct Table<C: ?Sized> {
+	codecs: Vec<( String, Box<C> )>,
+	names: BTreeMap<String, usize>,
+}
+
+impl<C: ?Sized> Table<C> {
+	fn new( ) -> Table<C> {
+		Table { codecs: Vec::new( ), names: BTreeMap::new( ) }
+	}
+
+	fn insert( &mut self, names: &[&str], codec: Box<C> ) {
+		let Some( first ) = names.first( ) else { return };
+		self.codecs.push( ( normalise( first ), codec ) );
+		for name in names {
+			self.names.insert( normalise( name ), self.codecs.len( ) - 1 );
+		}
+	}
+
+	fn get( &self, name: &str ) -> Option<&( String, Box<C> )> {
+		self.names.get( &normalise( name ) ).map( |&found| &self.codecs[ found ] )
+	}
+
+	fn pipeline<'r, I, S>( &'r self, names: I ) -> Result<Pipeline<'r, C>, UnknownCodec> where I: IntoIterator<Item = S>, S: AsRef<str> {
+		let mut stages = Vec::new( );
+		for name in names {
+			match self.get( name.as_ref( ) ) {
+				Some( ( canonical, codec ) ) => stages.push( ( canonical.as_str( ), &**codec ) ),
+				None => return Err( UnknownCodec { name: name.as_ref( ).to_string( ) } ),
+			}
+		}
+		Ok( Pipeline { stages } )
+	}
+}
+
+/// Names are compared as lower case, with `_` and `-` alike
+fn normalise( name: &str ) -> String {
+	name.trim( ).to_ascii_lowercase( ).replace( '_', "-" )
+}
+
+/// Splits a list such as `[url, html, rfc2047]` or `url | html` into its names
+fn split_names( spec: &str ) -> impl Iterator<Item = &str> {
+	spec.trim( ).trim_start_matches( '[' ).trim_end_matches( ']' )
+		.split( &[ ',', '|' ][ ..] )
+		.map( str::trim )
+		.filter( |name| !name.is_empty( ) )
+}
+
+impl Registry {
+	/// A registry of every codec in this crate, under its usual names
+	pub fn new( ) -> Registry {
+		let mut registry = Registry::empty( );
+
+		registry.register( &[ "html", "entities", "xhtml" ], Entities );
+		registry.register( &[ "html4" ], EntitySet::Html4 );
+		registry.register( &[ "html5" ], EntitySet::Html5 );
+		registry.register( &[ "xml-entities" ], EntitySet::Xml );
+		registry.register_decoder( &[ "xml" ], XmlOptions::default( ) );
+		registry.register( &[ "url", "percent", "rfc1738", "uri" ], Percent );
+		registry.register_encoder( &[ "url-path" ], EncodeSet::Path );
+		registry.register_encoder( &[ "url-full" ], EncodeSet::Url );
+		registry.register_decoder( &[ "quoted-printable", "qp" ], QuotedPrintable );
+		#[cfg( feature = "std" )]
+		registry.register_encoder( &[ "quoted-printable", "qp" ], QuotedPrintable );
+		#[cfg( feature = "std" )]
+		registry.register_decoder( &[ "rfc2047", "mime-header", "encoded-word" ], Rfc2047 );
+		registry.register( &[ "json" ], Json );
+		registry.register( &[ "c" ], Dialect::C );
+		registry.register( &[ "rust" ], Dialect::Rust );
+		registry.register( &[ "python" ], Dialect::Python );
+		registry.register( &[ "idna", "punycode" ], Idna );
+		registry.register( &[ "utf-7", "utf7" ], Utf7 );
+		registry.register( &[ "imap-utf-7", "imap-utf7", "imap" ], ImapUtf7 );
+		registry.register( &[ "base16", "hex" ], Base16 );
+		registry.register( &[ "base32" ], Base32::Standard );
+		registry.register( &[ "base32hex" ], Base32::Hex );
+		registry.register( &[ "base64", "b64" ], Base64::Standard );
+		registry.register( &[ "base64url" ], Base64::UrlSafe );
+		registry.register_decoder( &[ "ascii85", "base85" ], Base85::Ascii85 );
+		registry.register_decoder( &[ "z85" ], Base85::Z85 );
+		registry.register_encoder( &[ "html-text" ], HtmlContext::Text );
+		registry.register_encoder( &[ "html-attribute" ], HtmlContext::Attribute );
+		registry.register_encoder( &[ "html-url" ], HtmlContext::Url );
+		registry.register_encoder( &[ "html-script" ], HtmlContext::Script );
+		registry.register_encoder( &[ "html-style" ], HtmlContext::Style );
+
+		registry
+	}
+
+	/// A registry that knows no codecs at all
+	pub fn empty( ) -> Registry {
+		Registry { decoders: Table::new( ), encoders: Table::new( ) }
+	}
+
+	/// Makes `decoder` known by each of `names`, the first being the one pipelines report
+	pub fn register_decoder<D: Decode + 'static>( &mut self, names: &[&str], decoder: D ) {
+		self.decoders.insert( names, Box::new( decoder ) );
+	}
+
+	/// Makes `encoder` known by each of `names`, the first being the one pipelines report
+	pub fn register_encoder<E: Encode + 'static>( &mut self, names: &[&str], encoder: E ) {
+		self.encoders.insert( names, Box::new( encoder ) );
+	}
+
+	/// Registers a codec both ways
+	fn register<C: Decode + Encode + Copy + 'static>( &mut self, names: &[&str], codec: C ) {
+		self.register_decoder( names, codec );
+		self.register_encoder( names, codec );
+	}
+
+	/// The decoder known as `name`
+	pub fn decoder( &self, name: &str ) -> Option<&dyn Decode> {
+		self.decoders.get( name ).map( |( _, codec )| &**codec )
+	}
+
+	/// The encoder known as `name`
+	pub fn encoder( &self, name: &str ) -> Option<&dyn Encode> {
+		self.encoders.get( name ).map( |( _, codec )| &**codec )
+	}
+
+	/// Chains the decoders called `names`, to be applied in that order
+	pub fn decoders<I, S>( &self, names: I ) -> Result<Pipeline<'_, dyn Decode>, UnknownCodec> where I: IntoIterator<Item = S>, S: AsRef<str> {
+		self.decoders.pipeline( names )
+	}
+
+	/// Chains the encoders called `names`, to be applied in that order
+	pub fn encoders<I, S>( &self, names: I ) -> Result<Pipeline<'_, dyn Encode>, UnknownCodec> where I: IntoIterator<Item = S>, S: AsRef<str> {
+		self.encoders.pipeline( names )
+	}
+
+	/// Reads a list of decoder names, such as `[url, html, rfc2047]` or `url | html`,
+	/// into the pipeline that applies them in order
+	pub fn parse_decoders( &self, spec: &str ) -> Result<Pipeline<'_, dyn Decode>, UnknownCodec> {
+		self.decoders( split_names( spec ) )
+	}
+
+	/// Reads a list of encoder names into the pipeline that applies them in order
+	pub fn parse_encoders( &self, spec: &str ) -> Result<Pipeline<'_, dyn Encode>, UnknownCodec> {
+		self.encoders( split_names( spec ) )
+	}
+}
+
+impl Default for Registry {
+	fn default( ) -> Registry {
+		Registry::new( )
+	}
+}
+
+/// Codecs from a `Registry`, applied one after another
+///
+/// A pipeline is itself a `Decode` or `Encode`, so it can be used wherever one codec could.
+pub struct Pipeline<'r, C: ?Sized + 'r> {
+	stages: Vec<( &'r str, &'r C )>,
+}
+
+impl<'r, C: ?Sized + 'r> Pipeline<'r, C> {
+	/// Each stage, in order, with the name it was registered under first
+	pub fn stages( &self ) -> &[( &'r str, &'r C )] {
+		&self.stages
+	}
+}
+
+impl<'r> Decode for Pipeline<'r, dyn Decode + 'r> {
+	fn decode<'a>( &self, s: &'a str ) -> Result<Cow<'a, str>, DecodeError> {
+		let mut allo: Cow<'a, str> = s.into( );
+		for &( _, stage ) in &self.stages {
+			allo = match allo {
+				Cow::Borrowed( s ) => stage.decode( s )?,
+				Cow::Owned( s ) => stage.decode( &s )?.into_owned( ).into( ),
+			};
+		}
+		Ok( allo )
+	}
+}
+
+impl<'r> Encode for Pipeline<'r, dyn Encode + 'r> {
+	fn encode<'a>( &self, s: &'a str ) -> Cow<'a, str> {
+		let mut allo: Cow<'a, str> = s.into( );
+		for &( _, stage ) in &self.stages {
+			allo = match allo {
+				Cow::Borrowed( s ) => stage.encode( s ),
+				Cow::Owned( s ) => stage.encode( &s ).into_owned( ).into( ),
+			};
+		}
+		allo
+	}
+}
+
+
+#[cfg( test )]
+mod tests {
+
+	use super::*;
+
+	#[test]
+	fn test_registry( ) {
+		let registry = Registry::new( );
+		assert!( registry.decoder( "HTML" ).is_some( ) );
+		assert!( registry.decoder( "Quoted_Printable" ).is_some( ) );
+		assert!( registry.decoder( "html-script" ).is_none( ) );
+		assert!( registry.encoder( "html-script" ).is_some( ) );
+		assert!( registry.encoder( "z85" ).is_none( ) );
+		assert_eq!( registry.parse_decoders( "url, rot13" ).err( ),	Some( UnknownCodec { name: "rot13".to_string( ) } ) );
+
+		let pipeline = registry.parse_decoders( "[ percent, b64, Entities ]" ).unwrap( );
+		let names: Vec<&str> = pipeline.stages( ).iter( ).map( |&( name, _ )| name ).collect( );
+		assert_eq!( names,		vec![ "url", "base64", "html" ] );
+		assert_eq!( pipeline.decode( "Rmlza%43AmYW1wOyBDaGlwcw==" ),	Ok( "Fish & Chips".into( ) ) );
+		assert_eq!( registry.parse_decoders( "url | base64" ).unwrap( ).decode( "%%%" ),	Err( DecodeError::InvalidByte { offset: 0, byte: b'%' } ) );
+
+		let unchanged = registry.parse_decoders( "" ).unwrap( ).decode( "&amp;" ).unwrap( );
+		assert!( match unchanged { Cow::Borrowed( _ ) => true, Cow::Owned( _ ) => false } );
+
+		assert_eq!( registry.encoders( [ "html", "url" ] ).unwrap( ).encode( "<&>" ),	"%26lt%3B%26amp%3B%26gt%3B" );
+	}
+
+	#[test]
+	fn test_registry_overrides( ) {
+		let mut registry = Registry::empty( );
+		assert!( registry.decoder( "html" ).is_none( ) );
+		registry.register_decoder( &[ "html", "entities" ], EntitySet::Html5 );
+		registry.register_decoder( &[ "entities" ], EntitySet::Xml );
+		assert_eq!( registry.parse_decoders( "html" ).unwrap( ).decode( "&hearts;&amp;" ),		Ok( "♥&".into( ) ) );
+		assert_eq!( registry.parse_decoders( "entities" ).unwrap( ).decode( "&hearts;&amp;" ),	Ok( "&hearts;&".into( ) ) );
+	}
+}
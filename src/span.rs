@@ -0,0 +1,205 @@
+// Where each piece of decoded text came from in the original
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// A stretch of decoded text and the stretch of the original it came from
+#[derive( Clone, Debug, PartialEq, Eq )]
+pub struct Segment {
+	/// Byte offsets in the decoded text
+	pub decoded: Range<usize>,
+	/// Byte offsets in the original text
+	pub original: Range<usize>,
+	/// Whether the original was rewritten, as an escape is, rather than copied as it was
+	pub replaced: bool,
+}
+
+/// The link between decoded text and the original it was decoded from
+///
+/// The segments cover both texts from start to end, in order. Inside a
+/// copied segment every offset has its counterpart; inside a replaced one,
+/// such as `&amp;` becoming `&`, only the ends do, so the mapping snaps to them.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{decode_entities_mapped, EntitySet};
+/// let ( decoded, map ) = decode_entities_mapped( "Fish &amp; Chips", EntitySet::Xhtml1 );
+/// assert_eq!( decoded, "Fish & Chips" );
+/// assert_eq!( map.to_original( 7 ), 11 );
+/// assert_eq!( map.to_decoded( 11 ), 7 );
+/// // the & of the decoded text highlights all of &amp;
+/// assert_eq!( map.original_range( 5..6 ), 5..10 );
+/// ```
+#[derive( Clone, Debug, Default, PartialEq, Eq )]
+pub struct OffsetMap {
+	segments: Vec<Segment>,
+}
+
+impl OffsetMap {
+	/// The segments, in order
+	pub fn segments( &self ) -> &[Segment] {
+		&self.segments
+	}
+
+	/// The offset in the original for `decoded`; inside a replacement, its start
+	pub fn to_original( &self, decoded: usize ) -> usize {
+		self.map( decoded, false, forward )
+	}
+
+	/// The offset in the decoded text for `original`; inside a replacement, its start
+	pub fn to_decoded( &self, original: usize ) -> usize {
+		self.map( original, false, backward )
+	}
+
+	/// The smallest stretch of the original that holds all of `decoded`
+	pub fn original_range( &self, decoded: Range<usize> ) -> Range<usize> {
+		self.map( decoded.start, false, forward )..self.map( decoded.end, true, forward )
+	}
+
+	/// The smallest stretch of the decoded text that holds all of `original`
+	pub fn decoded_range( &self, original: Range<usize> ) -> Range<usize> {
+		self.map( original.start, false, backward )..self.map( original.end, true, backward )
+	}
+
+	/// Carries `at` across from one side to the other, rounding into a
+	/// replacement towards its start, or towards its end when `end`
+	fn map( &self, at: usize, end: bool, side: fn( &Segment ) -> ( &Range<usize>, &Range<usize> ) ) -> usize {
+		let found = if end {
+			self.segments.partition_point( |segment| side( segment ).0.end < at )
+		} else {
+			self.segments.partition_point( |segment| side( segment ).0.end <= at )
+		};
+		let segment = match self.segments.get( found ) {
+			Some( segment ) => segment,
+			None => return self.segments.last( ).map_or( at, |segment| side( segment ).1.end ),
+		};
+		let ( from, to ) = side( segment );
+		if at <= from.start {
+			to.start
+		} else if at >= from.end {
+			to.end
+		} else if !segment.replaced {
+			to.start + ( at - from.start )
+		} else if end {
+			to.end
+		} else {
+			to.start
+		}
+	}
+}
+
+/// A segment's stretches, decoded first
+fn forward( segment: &Segment ) -> ( &Range<usize>, &Range<usize> ) {
+	( &segment.decoded, &segment.original )
+}
+
+/// A segment's stretches, original first
+fn backward( segment: &Segment ) -> ( &Range<usize>, &Range<usize> ) {
+	( &segment.original, &segment.decoded )
+}
+
+/// Text rebuilt from `source` one replacement at a time, noting where each
+/// piece came from when asked to
+pub(crate) struct Rewrite<'a> {
+	source: &'a str,
+	allo: String,
+	copied: usize,
+	changed: bool,
+	segments: Option<Vec<Segment>>,
+}
+
+impl<'a> Rewrite<'a> {
+	pub(crate) fn new( source: &'a str ) -> Rewrite<'a> {
+		Rewrite { source, allo: String::new( ), copied: 0, changed: false, segments: None }
+	}
+
+	pub(crate) fn mapped( source: &'a str ) -> Rewrite<'a> {
+		Rewrite { segments: Some( Vec::new( ) ), ..Rewrite::new( source ) }
+	}
+
+	pub(crate) fn source( &self ) -> &'a str {
+		self.source
+	}
+
+	/// Copies the source up to `start`, then writes `value` in place of `start..end`
+	pub(crate) fn replace( &mut self, start: usize, end: usize, value: &str ) {
+		self.copy_to( start );
+		if let Some( ref mut segments ) = self.segments {
+			let decoded = self.allo.len( )..self.allo.len( ) + value.len( );
+			segments.push( Segment { decoded, original: start..end, replaced: true } );
+		}
+		self.allo.push_str( value );
+		self.copied = end;
+		self.changed = true;
+	}
+
+	fn copy_to( &mut self, end: usize ) {
+		if end <= self.copied {
+			return;
+		}
+		if let Some( ref mut segments ) = self.segments {
+			let decoded = self.allo.len( )..self.allo.len( ) + end - self.copied;
+			segments.push( Segment { decoded, original: self.copied..end, replaced: false } );
+		}
+		self.allo.push_str( &self.source[ self.copied..end ] );
+		self.copied = end;
+	}
+
+	/// The rewritten text, borrowed when nothing was replaced, and its map when `mapped`
+	pub(crate) fn finish( mut self ) -> ( Cow<'a, str>, OffsetMap ) {
+		if !self.changed {
+			let segments = match self.segments {
+				Some( _ ) if !self.source.is_empty( ) => vec![ Segment { decoded: 0..self.source.len( ), original: 0..self.source.len( ), replaced: false } ],
+				_ => Vec::new( ),
+			};
+			return ( self.source.into( ), OffsetMap { segments } );
+		}
+		let length = self.source.len( );
+		self.copy_to( length );
+		( self.allo.into( ), OffsetMap { segments: self.segments.unwrap_or_default( ) } )
+	}
+}
+
+
+#[cfg( test )]
+mod tests {
+
+	use super::*;
+
+	#[test]
+	fn test_offset_map( ) {
+		let mut rewrite = Rewrite::mapped( "a&lt;b&gt;&gt;c" );
+		rewrite.replace( 1, 5, "<" );
+		rewrite.replace( 6, 10, ">" );
+		rewrite.replace( 10, 14, ">" );
+		let ( decoded, map ) = rewrite.finish( );
+		assert_eq!( decoded,	"a<b>>c" );
+		assert_eq!( map.segments( ).len( ),	6 );
+		assert_eq!( map.to_original( 0 ),	0 );
+		assert_eq!( map.to_original( 2 ),	5 );
+		assert_eq!( map.to_original( 4 ),	10 );
+		assert_eq!( map.to_original( 6 ),	15 );
+		assert_eq!( map.to_original( 99 ),	15 );
+		assert_eq!( map.to_decoded( 3 ),	1 );
+		assert_eq!( map.to_decoded( 11 ),	4 );
+		assert_eq!( map.original_range( 1..4 ),	1..10 );
+		assert_eq!( map.original_range( 3..3 ),	6..6 );
+		assert_eq!( map.decoded_range( 2..8 ),	1..4 );
+
+		// Dropped text leaves an empty stretch of decoded text
+		let mut rewrite = Rewrite::mapped( "a  b" );
+		rewrite.replace( 1, 3, "" );
+		let ( decoded, map ) = rewrite.finish( );
+		assert_eq!( decoded,	"ab" );
+		assert_eq!( map.to_original( 1 ),	3 );
+		assert_eq!( map.decoded_range( 1..3 ),	1..1 );
+
+		let ( decoded, map ) = Rewrite::mapped( "abc" ).finish( );
+		assert!( match decoded { Cow::Borrowed( _ ) => true, Cow::Owned( _ ) => false } );
+		assert_eq!( map.to_original( 2 ),	2 );
+		assert_eq!( Rewrite::new( "abc" ).finish( ).1,	OffsetMap::default( ) );
+	}
+}
@@ -0,0 +1,616 @@
+// Decoding and encoding as data passes through, for inputs too large to hold at once
+//
+// Each adapter keeps back only the few bytes at the end of a buffer that may be
+// the start of an escape, so memory stays constant however long the stream.
+
+use std::borrow::Cow;
+use std::cmp;
+use std::io::{self, Read, Write};
+
+use token::{reference, Reference};
+use {decode_quoted_printable_bytes, decode_rfc1738_bytes, EncodeSet, EntitySet};
+
+/// How much is read from the inner reader at a time
+pub(crate) const CHUNK: usize = 8 * 1024;
+
+/// The longest entity a decoder waits for, `&CounterClockwiseContourIntegral;` being the longest name
+const LONGEST_ENTITY: usize = 40;
+
+/// The escapement a reading adapter undoes
+#[derive( Clone, Copy )]
+pub(crate) enum Unescape<'s> {
+	Entities( EntitySet<'s> ),
+	Percent,
+	QuotedPrintable,
+}
+
+impl<'s> Unescape<'s> {
+	/// How many bytes at the end of `s` may be an unfinished escape
+	fn held( self, s: &[u8] ) -> usize {
+		match self {
+			Unescape::Entities( _ ) => held_entity( s ),
+			Unescape::Percent => held_triplet( s, b'%' ),
+			// Up to `=\r`, which may yet become a soft line break
+			Unescape::QuotedPrintable => held_triplet( s, b'=' ),
+		}
+	}
+
+	fn apply<'a>( self, s: &'a [u8] ) -> Cow<'a, [u8]> {
+		match self {
+			Unescape::Entities( set ) => unescape_entities( s, set ),
+			Unescape::Percent => decode_rfc1738_bytes( s ),
+			Unescape::QuotedPrintable => decode_quoted_printable_bytes( s ),
+		}
+	}
+}
+
+/// Decodes what is read from an inner reader, a chunk at a time, whether blocking or not
+pub(crate) struct Unescaper<'s> {
+	unescape: Unescape<'s>,
+	pending: Vec<u8>,
+	decoded: Vec<u8>,
+	at: usize,
+	eof: bool,
+}
+
+impl<'s> Unescaper<'s> {
+	pub(crate) fn new( unescape: Unescape<'s> ) -> Unescaper<'s> {
+		Unescaper { unescape, pending: Vec::new( ), decoded: Vec::new( ), at: 0, eof: false }
+	}
+
+	/// Fills `buf` with decoded bytes, or returns `None` when more must be read first
+	pub(crate) fn take( &mut self, buf: &mut [u8] ) -> Option<usize> {
+		if self.at == self.decoded.len( ) {
+			return if self.eof { Some( 0 ) } else { None };
+		}
+		let length = cmp::min( buf.len( ), self.decoded.len( ) - self.at );
+		buf[ ..length ].copy_from_slice( &self.decoded[ self.at..self.at + length ] );
+		self.at += length;
+		Some( length )
+	}
+
+	/// Decodes a chunk from the inner reader, but for any unfinished escape at its end;
+	/// an empty chunk marks the end of the input, and decodes everything
+	pub(crate) fn feed( &mut self, chunk: &[u8] ) {
+		self.eof = chunk.is_empty( );
+		self.pending.extend_from_slice( chunk );
+
+		let ready = if self.eof { self.pending.len( ) } else { self.pending.len( ) - self.unescape.held( &self.pending ) };
+		self.decoded.clear( );
+		self.decoded.extend_from_slice( &self.unescape.apply( &self.pending[ ..ready ] ) );
+		self.pending.drain( ..ready );
+		self.at = 0;
+	}
+}
+
+/// Reads into `buf` from `inner`, through `unescaper`
+fn read_through<R: Read>( inner: &mut R, unescaper: &mut Unescaper, buf: &mut [u8] ) -> io::Result<usize> {
+	loop {
+		if let Some( length ) = unescaper.take( buf ) {
+			return Ok( length );
+		}
+		let mut chunk = [ 0; CHUNK ];
+		let length = inner.read( &mut chunk )?;
+		unescaper.feed( &chunk[ ..length ] );
+	}
+}
+
+/// How many bytes at the end of `s` may be the start of a `%XX` or `=XX` escape
+fn held_triplet( s: &[u8], escape: u8 ) -> usize {
+	match s.iter( ).rev( ).take( 2 ).position( |&byte| byte == escape ) {
+		Some( position ) => position + 1,
+		None => 0,
+	}
+}
+
+/// How many bytes at the end of `s` may be the start of an entity
+fn held_entity( s: &[u8] ) -> usize {
+	match s.iter( ).rev( ).take( LONGEST_ENTITY ).position( |&byte| !byte.is_ascii_alphanumeric( ) && byte != b'#' ) {
+		Some( position ) if s[ s.len( ) - position - 1 ] == b'&' => position + 1,
+		_ => 0,
+	}
+}
+
+/// Reads the entity at the start of `s`, returning its replacement and its length
+///
+/// References are read as `decode_entities` reads them, so `&#X41;` is left as found.
+fn entity_at( s: &[u8], set: EntitySet ) -> Option<( Cow<'static, str>, usize )> {
+	let end = s.iter( ).take( LONGEST_ENTITY ).position( |&byte| byte == b';' )?;
+	let body = ::std::str::from_utf8( &s[ 1..end + 1 ] ).ok( )?;
+
+	match reference( body )? {
+		( Reference::Named( name ), length ) => set.get( name ).map( |value| ( value.to_string( ).into( ), 1 + length ) ),
+		( Reference::Numeric( codepoint ), length ) => {
+			let c = ::std::char::from_u32( codepoint ).unwrap_or( '\u{FFFD}' );
+			Some( ( c.to_string( ).into( ), 1 + length ) )
+		},
+	}
+}
+
+/// Decodes the entities in `s` in a single pass, passing other bytes through untouched
+fn unescape_entities<'a>( s: &'a [u8], set: EntitySet ) -> Cow<'a, [u8]> {
+
+	if !s.contains( &b'&' ) {
+		return s.into( );
+	}
+
+	let mut allo = Vec::with_capacity( s.len( ) );
+	let mut i = 0;
+	while i < s.len( ) {
+		let entity = if s[ i ] == b'&' { entity_at( &s[ i..], set ) } else { None };
+		match entity {
+			Some( ( value, length ) ) => {
+				allo.extend_from_slice( value.as_bytes( ) );
+				i += length;
+			},
+			None => {
+				allo.push( s[ i ] );
+				i += 1;
+			}
+		}
+	}
+
+	allo.into( )
+}
+
+/// Decodes HTML/XML entities from a reader, in constant memory
+///
+/// Unlike `decode_entities`, each entity is decoded once, so `&amp;lt;` reads
+/// as `&lt;`. Bytes that are not part of an entity pass through untouched,
+/// whatever their charset.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use mung::EntityDecoder;
+/// let mut text = String::new( );
+/// EntityDecoder::new( &b"Fish &amp; Chips &#x2665;"[ ..] ).read_to_string( &mut text ).unwrap( );
+/// assert_eq!( text, "Fish & Chips ♥" );
+/// ```
+pub struct EntityDecoder<'s, R> {
+	inner: R,
+	unescaper: Unescaper<'s>,
+}
+
+impl<'s, R: Read> EntityDecoder<'s, R> {
+	/// Decodes the names `decode_entities` knows
+	pub fn new( inner: R ) -> EntityDecoder<'s, R> {
+		EntityDecoder::with_set( inner, EntitySet::Xhtml1 )
+	}
+
+	/// Decodes only the names in `set`
+	///
+	/// Only names of ASCII letters and digits are read, as `decode_entities`
+	/// reads them, so a `Custom` or `Table` name such as `a.b` or `x-y` is
+	/// never decoded.
+	pub fn with_set( inner: R, set: EntitySet<'s> ) -> EntityDecoder<'s, R> {
+		EntityDecoder { inner, unescaper: Unescaper::new( Unescape::Entities( set ) ) }
+	}
+
+	/// Gives back the inner reader
+	pub fn into_inner( self ) -> R {
+		self.inner
+	}
+}
+
+impl<'s, R: Read> Read for EntityDecoder<'s, R> {
+	fn read( &mut self, buf: &mut [u8] ) -> io::Result<usize> {
+		read_through( &mut self.inner, &mut self.unescaper, buf )
+	}
+}
+
+/// Decodes percent escapes from a reader into the bytes they stand for, in constant memory
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use mung::PercentDecoder;
+/// let mut bytes = Vec::new( );
+/// PercentDecoder::new( &b"%3Fsource%3D%2Fdata%20here"[ ..] ).read_to_end( &mut bytes ).unwrap( );
+/// assert_eq!( bytes, b"?source=/data here" );
+/// ```
+pub struct PercentDecoder<R> {
+	inner: R,
+	unescaper: Unescaper<'static>,
+}
+
+impl<R: Read> PercentDecoder<R> {
+	pub fn new( inner: R ) -> PercentDecoder<R> {
+		PercentDecoder { inner, unescaper: Unescaper::new( Unescape::Percent ) }
+	}
+
+	/// Gives back the inner reader
+	pub fn into_inner( self ) -> R {
+		self.inner
+	}
+}
+
+impl<R: Read> Read for PercentDecoder<R> {
+	fn read( &mut self, buf: &mut [u8] ) -> io::Result<usize> {
+		read_through( &mut self.inner, &mut self.unescaper, buf )
+	}
+}
+
+/// Decodes a quoted-printable body from a reader, in constant memory
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use mung::QuotedPrintableDecoder;
+/// let mut bytes = Vec::new( );
+/// QuotedPrintableDecoder::new( &b"caf=C3=A9 au=\r\nlait"[ ..] ).read_to_end( &mut bytes ).unwrap( );
+/// assert_eq!( bytes, "café aulait".as_bytes( ) );
+/// ```
+pub struct QuotedPrintableDecoder<R> {
+	inner: R,
+	unescaper: Unescaper<'static>,
+}
+
+impl<R: Read> QuotedPrintableDecoder<R> {
+	pub fn new( inner: R ) -> QuotedPrintableDecoder<R> {
+		QuotedPrintableDecoder { inner, unescaper: Unescaper::new( Unescape::QuotedPrintable ) }
+	}
+
+	/// Gives back the inner reader
+	pub fn into_inner( self ) -> R {
+		self.inner
+	}
+}
+
+impl<R: Read> Read for QuotedPrintableDecoder<R> {
+	fn read( &mut self, buf: &mut [u8] ) -> io::Result<usize> {
+		read_through( &mut self.inner, &mut self.unescaper, buf )
+	}
+}
+
+/// The escapement a writing adapter applies
+#[derive( Clone, Copy )]
+pub(crate) enum Escape<'s> {
+	Entities( EntitySet<'s> ),
+	Percent( EncodeSet ),
+	QuotedPrintable,
+}
+
+/// Encodes what is written to an inner writer, whether blocking or not
+pub(crate) struct Escaper<'s> {
+	escape: Escape<'s>,
+	pending: Vec<u8>,
+	line: usize,
+}
+
+impl<'s> Escaper<'s> {
+	pub(crate) fn new( escape: Escape<'s> ) -> Escaper<'s> {
+		Escaper { escape, pending: Vec::new( ), line: 0 }
+	}
+
+	/// Encodes `buf`, holding back the last bytes when their encoding depends on
+	/// what follows, unless `finishing`
+	pub(crate) fn encode( &mut self, buf: &[u8], finishing: bool ) -> Vec<u8> {
+		self.pending.extend_from_slice( buf );
+		let pending = ::std::mem::take( &mut self.pending );
+		let mut allo = Vec::with_capacity( pending.len( ) + 16 );
+
+		match self.escape {
+			// The markup characters are all ASCII, so there is never anything to hold back
+			Escape::Entities( set ) => for &byte in &pending {
+				match byte {
+					b'&' | b'<' | b'>' | b'"' | b'\'' => match set.name_of( byte as char ) {
+						Some( name ) => { allo.push( b'&' ); allo.extend_from_slice( name.as_bytes( ) ); allo.push( b';' ); },
+						None => allo.extend_from_slice( format!( "&#x{:x};", byte ).as_bytes( ) ),
+					},
+					byte => allo.push( byte ),
+				}
+			},
+			Escape::Percent( set ) => {
+				let held = if set == EncodeSet::Url && !finishing { held_triplet( &pending, b'%' ) } else { 0 };
+				let ready = pending.len( ) - held;
+				let mut encoded = String::with_capacity( ready + 16 );
+				set.encode_into( &pending[ ..ready ], &mut encoded );
+				allo.extend_from_slice( encoded.as_bytes( ) );
+				self.pending.extend_from_slice( &pending[ ready..] );
+			},
+			Escape::QuotedPrintable => self.quote( &pending, finishing, &mut allo ),
+		}
+
+		allo
+	}
+
+	/// Adds `token` to the line, breaking it first if it would grow too long
+	fn push( &mut self, allo: &mut Vec<u8>, token: &[u8] ) {
+		// 75 leaves room for the = of a soft line break
+		if self.line + token.len( ) > 75 {
+			allo.extend_from_slice( b"=\r\n" );
+			self.line = 0;
+		}
+		allo.extend_from_slice( token );
+		self.line += token.len( );
+	}
+
+	/// Quoted-printable encodes `pending`, holding back a last byte whose encoding depends on the next one
+	fn quote( &mut self, pending: &[u8], finishing: bool, allo: &mut Vec<u8> ) {
+		let mut i = 0;
+		while i < pending.len( ) {
+			let byte = pending[ i ];
+			let next = pending.get( i + 1 ).cloned( );
+			if next.is_none( ) && !finishing && ( byte == b' ' || byte == b'\t' || byte == b'\r' ) {
+				self.pending.push( byte );
+				break;
+			}
+
+			match ( byte, next ) {
+				( b'\n', _ ) => {
+					allo.push( b'\n' );
+					self.line = 0;
+				},
+				( b'\r', Some( b'\n' ) ) => {
+					allo.extend_from_slice( b"\r\n" );
+					self.line = 0;
+					i += 1;
+				},
+				( b' ', Some( b'\n' ) ) | ( b' ', Some( b'\r' ) ) | ( b' ', None ) |
+				( b'\t', Some( b'\n' ) ) | ( b'\t', Some( b'\r' ) ) | ( b'\t', None ) => {
+					let token = format!( "={:02X}", byte );
+					self.push( allo, token.as_bytes( ) );
+				},
+				( b' ', _ ) | ( b'\t', _ ) | ( b'!' ..= b'<', _ ) | ( b'>' ..= b'~', _ ) => self.push( allo, &[ byte ] ),
+				_ => {
+					let token = format!( "={:02X}", byte );
+					self.push( allo, token.as_bytes( ) );
+				}
+			}
+			i += 1;
+		}
+	}
+}
+
+/// Encodes the markup-significant characters written to it as entities
+///
+/// Those characters are all ASCII, so this needs no state between writes.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use mung::EntityEncoder;
+/// let mut encoder = EntityEncoder::new( Vec::new( ) );
+/// encoder.write_all( b"Fish & <Chips>" ).unwrap( );
+/// assert_eq!( encoder.into_inner( ), b"Fish &amp; &lt;Chips&gt;" );
+/// ```
+pub struct EntityEncoder<'s, W> {
+	inner: W,
+	escaper: Escaper<'s>,
+}
+
+impl<'s, W: Write> EntityEncoder<'s, W> {
+	/// Encodes as `encode_entities` does
+	pub fn new( inner: W ) -> EntityEncoder<'s, W> {
+		EntityEncoder::with_set( inner, EntitySet::Html4 )
+	}
+
+	/// Encodes with the names in `set`
+	pub fn with_set( inner: W, set: EntitySet<'s> ) -> EntityEncoder<'s, W> {
+		EntityEncoder { inner, escaper: Escaper::new( Escape::Entities( set ) ) }
+	}
+
+	/// Gives back the inner writer
+	pub fn into_inner( self ) -> W {
+		self.inner
+	}
+}
+
+impl<'s, W: Write> Write for EntityEncoder<'s, W> {
+	fn write( &mut self, buf: &[u8] ) -> io::Result<usize> {
+		let allo = self.escaper.encode( buf, false );
+		self.inner.write_all( &allo )?;
+		Ok( buf.len( ) )
+	}
+
+	fn flush( &mut self ) -> io::Result<( )> {
+		self.inner.flush( )
+	}
+}
+
+/// Percent-encodes what is written to it, as `encode_rfc1738` does
+///
+/// With `EncodeSet::Url` a `%` at the end of a write is held back, as it
+/// may begin an escape that is kept; call `finish` to write it out.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use mung::{EncodeSet, PercentEncoder};
+/// let mut encoder = PercentEncoder::new( Vec::new( ), EncodeSet::Url );
+/// encoder.write_all( b"/a b%" ).unwrap( );
+/// encoder.write_all( b"20c" ).unwrap( );
+/// assert_eq!( encoder.finish( ).unwrap( ), b"/a%20b%20c" );
+/// ```
+pub struct PercentEncoder<W> {
+	inner: W,
+	escaper: Escaper<'static>,
+}
+
+impl<W: Write> PercentEncoder<W> {
+	pub fn new( inner: W, set: EncodeSet ) -> PercentEncoder<W> {
+		PercentEncoder { inner, escaper: Escaper::new( Escape::Percent( set ) ) }
+	}
+
+	/// Writes out anything held back, and gives back the inner writer
+	pub fn finish( mut self ) -> io::Result<W> {
+		let allo = self.escaper.encode( &[ ], true );
+		self.inner.write_all( &allo )?;
+		Ok( self.inner )
+	}
+}
+
+impl<W: Write> Write for PercentEncoder<W> {
+	fn write( &mut self, buf: &[u8] ) -> io::Result<usize> {
+		let allo = self.escaper.encode( buf, false );
+		self.inner.write_all( &allo )?;
+		Ok( buf.len( ) )
+	}
+
+	fn flush( &mut self ) -> io::Result<( )> {
+		self.inner.flush( )
+	}
+}
+
+/// Encodes what is written to it as a quoted-printable body
+/// See: Multipurpose Internet Mail Extensions (MIME) Part One https://tools.ietf.org/html/rfc2045#section-6.7
+///
+/// Line breaks are kept as written, white space at the end of a line is
+/// encoded, and lines longer than 76 characters are split with soft line
+/// breaks. A space, tab or carriage return at the end of a write is held back
+/// until the next byte shows what follows it; call `finish` to write it out.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use mung::QuotedPrintableEncoder;
+/// let mut encoder = QuotedPrintableEncoder::new( Vec::new( ) );
+/// encoder.write_all( "café = coffee \n".as_bytes( ) ).unwrap( );
+/// assert_eq!( encoder.finish( ).unwrap( ), b"caf=C3=A9 =3D coffee=20\n" );
+/// ```
+pub struct QuotedPrintableEncoder<W> {
+	inner: W,
+	escaper: Escaper<'static>,
+}
+
+impl<W: Write> QuotedPrintableEncoder<W> {
+	pub fn new( inner: W ) -> QuotedPrintableEncoder<W> {
+		QuotedPrintableEncoder { inner, escaper: Escaper::new( Escape::QuotedPrintable ) }
+	}
+
+	/// Writes out anything held back, and gives back the inner writer
+	pub fn finish( mut self ) -> io::Result<W> {
+		let allo = self.escaper.encode( &[ ], true );
+		self.inner.write_all( &allo )?;
+		Ok( self.inner )
+	}
+}
+
+impl<W: Write> Write for QuotedPrintableEncoder<W> {
+	fn write( &mut self, buf: &[u8] ) -> io::Result<usize> {
+		let allo = self.escaper.encode( buf, false );
+		self.inner.write_all( &allo )?;
+		Ok( buf.len( ) )
+	}
+
+	fn flush( &mut self ) -> io::Result<( )> {
+		self.inner.flush( )
+	}
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	/// Hands out its bytes a few at a time, so escapes straddle the reads
+	struct Trickle<'a>( &'a [u8], usize );
+
+	impl<'a> Read for Trickle<'a> {
+		fn read( &mut self, buf: &mut [u8] ) -> io::Result<usize> {
+			let length = cmp::min( cmp::min( self.1, buf.len( ) ), self.0.len( ) );
+			buf[ ..length ].copy_from_slice( &self.0[ ..length ] );
+			self.0 = &self.0[ length..];
+			Ok( length )
+		}
+	}
+
+	fn read_all<R: Read>( mut reader: R ) -> Vec<u8> {
+		let mut allo = Vec::new( );
+		reader.read_to_end( &mut allo ).unwrap( );
+		allo
+	}
+
+	#[test]
+	fn test_entity_decoder( ) {
+
+		let html = "Best &amp; the &lt;Worst&gt; &#8800; &#x2665; &hearts;&nbsp;&fred; &#; &#x110000; & &amp;lt; end&";
+		let text = "Best & the <Worst> ≠ ♥ ♥\u{A0}&fred; &#; \u{FFFD} & &lt; end&";
+		for step in 1..12 {
+			assert_eq!( String::from_utf8( read_all( EntityDecoder::new( Trickle( html.as_bytes( ), step ) ) ) ).unwrap( ),	text );
+		}
+		assert_eq!( read_all( EntityDecoder::with_set( &b"&nbsp;&apos;"[ ..], EntitySet::Xml ) ),	b"&nbsp;'" );
+		assert_eq!( read_all( EntityDecoder::new( &b"\xff&amp;\xfe"[ ..] ) ),						b"\xff&\xfe" );
+		assert_eq!( read_all( EntityDecoder::new( &b"a\0b&#x41;&#X41;&#65"[ ..] ) ),				b"a\0bA&#X41;&#65" );
+		// A set that borrows from the stack, not only the built-in 'static ones
+		let names = vec![ ( "fred", "Fred" ), ( "amp", "&" ) ];
+		assert_eq!( read_all( EntityDecoder::with_set( &b"&fred;&amp;&lt;"[ ..], EntitySet::Custom( &names ) ) ),	b"Fred&&lt;" );
+	}
+
+	#[test]
+	fn test_percent_decoder( ) {
+
+		for step in 1..5 {
+			assert_eq!( read_all( PercentDecoder::new( Trickle( b"%3Fa%3d%2F%zz%4%", step ) ) ),	b"?a=/%zz%4%" );
+		}
+	}
+
+	#[test]
+	fn test_quoted_printable_decoder( ) {
+
+		for step in 1..5 {
+			assert_eq!( read_all( QuotedPrintableDecoder::new( Trickle( b"a=3Db =\r\nc=\nd=0D=0A=", step ) ) ),	b"a=b cd\r\n=" );
+		}
+	}
+
+	#[test]
+	fn test_entity_encoder( ) {
+
+		let mut encoder = EntityEncoder::with_set( Vec::new( ), EntitySet::Xml );
+		encoder.write_all( "'café' & ".as_bytes( ) ).unwrap( );
+		encoder.write_all( b"<tea>" ).unwrap( );
+		assert_eq!( String::from_utf8( encoder.into_inner( ) ).unwrap( ),	"&apos;café&apos; &amp; &lt;tea&gt;" );
+		let names = vec![ ( "amp", "&" ) ];
+		let mut encoder = EntityEncoder::with_set( Vec::new( ), EntitySet::Custom( &names ) );
+		encoder.write_all( b"<&>" ).unwrap( );
+		assert_eq!( encoder.into_inner( ),	b"&#x3c;&amp;&#x3e;" );
+	}
+
+	#[test]
+	fn test_percent_encoder( ) {
+
+		let mut encoder = PercentEncoder::new( Vec::new( ), EncodeSet::Component );
+		for byte in "a/b c%".bytes( ) {
+			encoder.write_all( &[ byte ] ).unwrap( );
+		}
+		assert_eq!( encoder.finish( ).unwrap( ),	b"a%2Fb%20c%25" );
+
+		let mut encoder = PercentEncoder::new( Vec::new( ), EncodeSet::Url );
+		for byte in "/%41%zz %".bytes( ) {
+			encoder.write_all( &[ byte ] ).unwrap( );
+		}
+		assert_eq!( encoder.finish( ).unwrap( ),	b"/%41%25zz%20%25" );
+	}
+
+	#[test]
+	fn test_quoted_printable_encoder( ) {
+
+		let mut encoder = QuotedPrintableEncoder::new( Vec::new( ) );
+		for byte in b"tab\t \r\nnull\x00 end \r" {
+			encoder.write_all( &[ *byte ] ).unwrap( );
+		}
+		assert_eq!( encoder.finish( ).unwrap( ),	&b"tab\t=20\r\nnull=00 end=20=0D"[ ..] );
+
+		let long = "x".repeat( 100 );
+		let mut encoder = QuotedPrintableEncoder::new( Vec::new( ) );
+		encoder.write_all( long.as_bytes( ) ).unwrap( );
+		let encoded = encoder.finish( ).unwrap( );
+		assert_eq!( encoded.split( |&byte| byte == b'\n' ).map( |line| line.len( ) ).collect::<Vec<_>>( ),	vec![ 77, 25 ] );
+
+		// Round trip, through both adapters
+		let original: Vec<u8> = ( 0..=255u8 ).chain( b"line  \nbreak=".iter( ).cloned( ) ).collect( );
+		let mut encoder = QuotedPrintableEncoder::new( Vec::new( ) );
+		encoder.write_all( &original ).unwrap( );
+		let encoded = encoder.finish( ).unwrap( );
+		for step in 1..5 {
+			assert_eq!( read_all( QuotedPrintableDecoder::new( Trickle( &encoded, step ) ) ),	original );
+		}
+	}
+}
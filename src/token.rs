@@ -0,0 +1,545 @@
+// Escaped text as a sequence of tokens, for linters, highlighters and custom transforms
+// Base-N encodings have no lexer: every character of them is encoded, so there is no literal text to tell apart
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use core::ops::Range;
+
+use memchr::{memchr, memchr2, memmem};
+
+use literal::{self, Escape};
+use xml::{self, PREDEFINED};
+use {decode_base64_bytes, hex_pair, json, unquote, utf7, Base64, Dialect, EntitySet};
+
+/// A piece of escaped text: either plain text, or one escape and what it stands for
+///
+/// Spans are byte offsets into the text being read.
+#[derive( Clone, Debug, PartialEq, Eq )]
+pub enum Token<'a> {
+	/// Text with no escape in it, to be taken as it is
+	Literal( &'a str ),
+	/// A named entity the set knows, such as `&amp;`
+	NamedEntity { name: &'a str, value: &'a str, span: Range<usize> },
+	/// A well-formed `&name;` that the set does not know, which decoders leave as found
+	UnknownEntity { name: &'a str, span: Range<usize> },
+	/// A numeric character reference, such as `&#38;` or `&#x26;`, which need not be a valid codepoint
+	NumericRef { codepoint: u32, span: Range<usize> },
+	/// A `%XX` URL escape
+	PercentTriplet { byte: u8, span: Range<usize> },
+	/// A quoted-printable `=XX` escape
+	QuotedByte { byte: u8, span: Range<usize> },
+	/// A quoted-printable soft line break, an `=` ending a line, which stands for nothing
+	SoftLineBreak { span: Range<usize> },
+	/// An RFC 2047 `=?charset?encoding?text?=` word, with the bytes its text carries
+	///
+	/// When the text cannot be decoded, `bytes` holds the text itself, as
+	/// RFC 2047 says it should then be shown.
+	EncodedWord { charset: &'a str, encoding: &'a str, bytes: Cow<'a, [u8]>, span: Range<usize> },
+	/// A backslash escape for one character, such as `\n` or `\u00e9`
+	///
+	/// `value` is `None` when the escape is well formed but names no
+	/// character, as `\U00110000` or a lone JSON surrogate does.
+	CharEscape { value: Option<char>, span: Range<usize> },
+	/// A C octal or `\x` escape, which stands for one byte of UTF-8
+	ByteEscape { byte: u8, span: Range<usize> },
+	/// A backslash ending a line, which joins it to the next and stands for nothing
+	LineContinuation { span: Range<usize> },
+	/// A UTF-7 run of base64 UTF-16, opened by `+` (`&` in IMAP), with the text it stands for
+	ShiftedRun { value: String, span: Range<usize> },
+	/// An entity the DOCTYPE of an XML document declares, with its replacement text as declared
+	///
+	/// The replacement text may itself hold references; `decode_xml` expands them.
+	DeclaredEntity { name: &'a str, replacement: String, span: Range<usize> },
+}
+
+/// An entity reference as written, before its name is looked up
+pub(crate) enum Reference<'b> {
+	Named( &'b str ),
+	/// Saturated at `u32::MAX`, which is no codepoint either
+	Numeric( u32 ),
+}
+
+/// Reads the entity reference whose text after the `&` starts `body`, with the length of `body` it took
+pub(crate) fn reference( body: &str ) -> Option<( Reference<'_>, usize )> {
+	let bytes = body.as_bytes( );
+	let ( skip, radix ) = match ( bytes.first( ), bytes.get( 1 ) ) {
+		( Some( &b'#' ), Some( &b'x' ) ) => ( 2, 16 ),
+		( Some( &b'#' ), _ ) => ( 1, 10 ),
+		_ => ( 0, 36 ),
+	};
+	let length = bytes[ skip..].iter( ).take_while( |byte| ( **byte as char ).is_digit( radix ) ).count( );
+	if length == 0 || bytes.get( skip + length ) != Some( &b';' ) {
+		return None;
+	}
+	let name = &body[ skip..skip + length ];
+
+	let reference = if radix == 36 {
+		Reference::Named( name )
+	} else {
+		Reference::Numeric( name.chars( ).fold( 0u32, |n, digit| n.saturating_mul( radix ).saturating_add( digit.to_digit( radix ).unwrap_or( 0 ) ) ) )
+	};
+	Some( ( reference, skip + length + 1 ) )
+}
+
+/// Reads the `=?charset?encoding?text?=` at the start of `s`, with its length
+pub(crate) fn encoded_word( s: &str ) -> Option<( &str, &str, &str, usize )> {
+	let mut parts = s[ 2..].splitn( 4, '?' );
+	let charset = parts.next( )?;
+	let encoding = parts.next( )?;
+	let text = parts.next( )?;
+	let rest = parts.next( )?;
+	if !rest.starts_with( '=' ) {
+		return None;
+	}
+	Some( ( charset, encoding, text, s.len( ) - rest.len( ) + 1 ) )
+}
+
+/// Which escapes a `Tokens` looks for
+#[derive( Clone )]
+enum Lexer<'a> {
+	Entities( EntitySet<'a> ),
+	Percent,
+	QuotedPrintable,
+	Rfc2047,
+	Json,
+	Literal( Dialect ),
+	Utf7 { imap: bool },
+	/// The entities the DOCTYPE declares, and where the document's body begins
+	Xml { declared: BTreeMap<String, String>, body: usize },
+}
+
+/// An iterator over the tokens of escaped text; see `entity_tokens` and its siblings
+///
+/// Joining the text of every token's span, and every `Literal`, gives back the input.
+#[derive( Clone )]
+pub struct Tokens<'a> {
+	s: &'a str,
+	at: usize,
+	lexer: Lexer<'a>,
+	pending: Option<Token<'a>>,
+}
+
+/// Reads text as literals, entities and numeric character references
+///
+/// An `&` that starts no well-formed reference stays in the literal text, as
+/// `decode_entities_with` leaves it. Each reference is a token of its own, so
+/// `&amp;amp;` is an `&amp;` followed by the literal `amp;`.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{entity_tokens, EntitySet, Token};
+/// let tokens: Vec<Token> = entity_tokens( "Fish &amp; Chips &fred;", EntitySet::Html4 ).collect( );
+/// assert_eq!( tokens, vec![
+///     Token::Literal( "Fish " ),
+///     Token::NamedEntity { name: "amp", value: "&", span: 5..10 },
+///     Token::Literal( " Chips " ),
+///     Token::UnknownEntity { name: "fred", span: 17..23 },
+/// ] );
+/// ```
+pub fn entity_tokens<'a>( s: &'a str, set: EntitySet<'a> ) -> Tokens<'a> {
+	Tokens::new( s, Lexer::Entities( set ) )
+}
+
+/// Reads a URL as literals and `%XX` triplets
+///
+/// # Examples
+///
+/// ```
+/// use mung::{percent_tokens, Token};
+/// let tokens: Vec<Token> = percent_tokens( "100%25" ).collect( );
+/// assert_eq!( tokens, vec![ Token::Literal( "100" ), Token::PercentTriplet { byte: b'%', span: 3..6 } ] );
+/// ```
+pub fn percent_tokens( s: &str ) -> Tokens<'_> {
+	Tokens::new( s, Lexer::Percent )
+}
+
+/// Reads quoted-printable as literals, `=XX` escapes and soft line breaks
+///
+/// # Examples
+///
+/// ```
+/// use mung::{quoted_printable_tokens, Token};
+/// let tokens: Vec<Token> = quoted_printable_tokens( "caf=E9=\r\n" ).collect( );
+/// assert_eq!( tokens, vec![
+///     Token::Literal( "caf" ),
+///     Token::QuotedByte { byte: 0xE9, span: 3..6 },
+///     Token::SoftLineBreak { span: 6..9 },
+/// ] );
+/// ```
+pub fn quoted_printable_tokens( s: &str ) -> Tokens<'_> {
+	Tokens::new( s, Lexer::QuotedPrintable )
+}
+
+/// Reads a mail header as literals and RFC 2047 encoded words
+///
+/// White space between two encoded words is a literal here, though
+/// `decode_rfc2047` drops it.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{rfc2047_tokens, Token};
+/// let tokens: Vec<Token> = rfc2047_tokens( "Re: =?ISO-8859-1?Q?caf=E9?=" ).collect( );
+/// assert_eq!( tokens, vec![
+///     Token::Literal( "Re: " ),
+///     Token::EncodedWord { charset: "ISO-8859-1", encoding: "Q", bytes: b"caf\xE9"[ ..].into( ), span: 4..27 },
+/// ] );
+/// ```
+pub fn rfc2047_tokens( s: &str ) -> Tokens<'_> {
+	Tokens::new( s, Lexer::Rfc2047 )
+}
+
+/// Reads the text of a JSON string as literals and backslash escapes
+///
+/// A surrogate pair such as `\ud83d\ude00` is one escape. A backslash that
+/// starts no escape JSON knows stays in the literal text.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{json_tokens, Token};
+/// let tokens: Vec<Token> = json_tokens( r"caf\u00e9\n" ).collect( );
+/// assert_eq!( tokens, vec![
+///     Token::Literal( "caf" ),
+///     Token::CharEscape { value: Some( 'é' ), span: 3..9 },
+///     Token::CharEscape { value: Some( '\n' ), span: 9..11 },
+/// ] );
+/// ```
+pub fn json_tokens( s: &str ) -> Tokens<'_> {
+	Tokens::new( s, Lexer::Json )
+}
+
+/// Reads the text of a string literal as literals and the backslash escapes of `dialect`
+///
+/// Escapes the dialect does not know stay in the literal text, as
+/// `decode_literal` leaves them.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{literal_tokens, Dialect, Token};
+/// let tokens: Vec<Token> = literal_tokens( r"caf\303\251\q", Dialect::C ).collect( );
+/// assert_eq!( tokens, vec![
+///     Token::Literal( "caf" ),
+///     Token::ByteEscape { byte: 0xC3, span: 3..7 },
+///     Token::ByteEscape { byte: 0xA9, span: 7..11 },
+///     Token::Literal( r"\q" ),
+/// ] );
+/// ```
+pub fn literal_tokens( s: &str, dialect: Dialect ) -> Tokens<'_> {
+	Tokens::new( s, Lexer::Literal( dialect ) )
+}
+
+/// Reads UTF-7 as literals and shifted runs
+///
+/// Bytes UTF-7 cannot hold stay in the literal text, though `decode_utf7`
+/// replaces them.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{utf7_tokens, Token};
+/// let tokens: Vec<Token> = utf7_tokens( "1 +- 1 = +ZeVnLIqe-" ).collect( );
+/// assert_eq!( tokens, vec![
+///     Token::Literal( "1 " ),
+///     Token::ShiftedRun { value: "+".into( ), span: 2..4 },
+///     Token::Literal( " 1 = " ),
+///     Token::ShiftedRun { value: "日本語".into( ), span: 9..19 },
+/// ] );
+/// ```
+pub fn utf7_tokens( s: &str ) -> Tokens<'_> {
+	Tokens::new( s, Lexer::Utf7 { imap: false } )
+}
+
+/// Reads an IMAP mailbox name as literals and shifted runs of modified UTF-7
+///
+/// # Examples
+///
+/// ```
+/// use mung::{imap_utf7_tokens, Token};
+/// let tokens: Vec<Token> = imap_utf7_tokens( "Entw&APw-rfe" ).collect( );
+/// assert_eq!( tokens[ 1 ], Token::ShiftedRun { value: "ü".into( ), span: 4..9 } );
+/// ```
+pub fn imap_utf7_tokens( s: &str ) -> Tokens<'_> {
+	Tokens::new( s, Lexer::Utf7 { imap: true } )
+}
+
+/// Reads an XML document as literals, entity references and character references
+///
+/// The five XML predefines are named entities, and entities the DOCTYPE
+/// declares are `DeclaredEntity` tokens. The DOCTYPE, comments and CDATA
+/// sections are literal text, as `decode_xml` copies them untouched.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{xml_tokens, Token};
+/// let document = r#"<!DOCTYPE memo [ <!ENTITY corp "ACME &amp; Sons"> ]><to>&corp;</to>"#;
+/// let tokens: Vec<Token> = xml_tokens( document ).collect( );
+/// assert_eq!( tokens[ 1 ], Token::DeclaredEntity { name: "corp", replacement: "ACME &amp; Sons".into( ), span: 56..62 } );
+/// ```
+pub fn xml_tokens( s: &str ) -> Tokens<'_> {
+	let ( declared, body ) = xml::doctype( s );
+	Tokens::new( s, Lexer::Xml { declared, body } )
+}
+
+impl<'a> Tokens<'a> {
+	fn new( s: &'a str, lexer: Lexer<'a> ) -> Tokens<'a> {
+		Tokens { s, at: 0, lexer, pending: None }
+	}
+
+	/// Where the next escape might start, at or after `from`
+	fn next_escape( &self, from: usize ) -> Option<usize> {
+		let rest = &self.s.as_bytes( )[ from..];
+		let found = match self.lexer {
+			Lexer::Entities( _ ) => memchr( b'&', rest ),
+			Lexer::Percent => memchr( b'%', rest ),
+			Lexer::QuotedPrintable => memchr( b'=', rest ),
+			Lexer::Rfc2047 => memmem::find( rest, b"=?" ),
+			Lexer::Json | Lexer::Literal( _ ) => memchr( b'\\', rest ),
+			Lexer::Utf7 { imap } => memchr( if imap { b'&' } else { b'+' }, rest ),
+			Lexer::Xml { body, .. } => {
+				// References in comments and CDATA sections are only text
+				let mut at = from.max( body );
+				while let Some( found ) = memchr2( b'&', b'<', &self.s.as_bytes( )[ at..] ) {
+					at += found;
+					match xml::verbatim_length( &self.s[ at..] ) {
+						Some( length ) => at += length,
+						None if self.s.as_bytes( )[ at ] == b'<' => at += 1,
+						None => return Some( at ),
+					}
+				}
+				return None;
+			},
+		};
+		found.map( |found| from + found )
+	}
+
+	/// Reads the escape at `start`, with where it ends
+	fn escape( &self, start: usize ) -> Option<( Token<'a>, usize )> {
+		let s = self.s;
+		let bytes = s.as_bytes( );
+		let pair = || if start + 2 < bytes.len( ) { hex_pair( bytes[ start + 1 ], bytes[ start + 2 ] ) } else { None };
+
+		match self.lexer {
+			Lexer::Entities( set ) => {
+				let ( reference, length ) = reference( &s[ start + 1..] )?;
+				let span = start..start + 1 + length;
+				let token = match reference {
+					Reference::Named( name ) => match set.get( name ) {
+						Some( value ) => Token::NamedEntity { name, value, span: span.clone( ) },
+						None => Token::UnknownEntity { name, span: span.clone( ) },
+					},
+					Reference::Numeric( codepoint ) => Token::NumericRef { codepoint, span: span.clone( ) },
+				};
+				Some( ( token, span.end ) )
+			},
+			Lexer::Percent => pair( ).map( |byte| ( Token::PercentTriplet { byte, span: start..start + 3 }, start + 3 ) ),
+			Lexer::QuotedPrintable => {
+				let length = if bytes[ start + 1..].starts_with( b"\r\n" ) { 3 } else if bytes[ start + 1..].starts_with( b"\n" ) { 2 } else { 0 };
+				if length > 0 {
+					return Some( ( Token::SoftLineBreak { span: start..start + length }, start + length ) );
+				}
+				pair( ).map( |byte| ( Token::QuotedByte { byte, span: start..start + 3 }, start + 3 ) )
+			},
+			Lexer::Rfc2047 => {
+				let ( charset, encoding, text, length ) = encoded_word( &s[ start..] )?;
+				let bytes = if encoding.eq_ignore_ascii_case( "b" ) {
+					decode_base64_bytes( text.as_bytes( ), Base64::Mime ).map( Cow::Owned ).unwrap_or_else( |_| text.as_bytes( ).into( ) )
+				} else if encoding.eq_ignore_ascii_case( "q" ) {
+					unquote( text.as_bytes( ), true )
+				} else {
+					text.as_bytes( ).into( )
+				};
+				Some( ( Token::EncodedWord { charset, encoding, bytes, span: start..start + length }, start + length ) )
+			},
+			Lexer::Json => {
+				let ( length, value ) = json::escape( &s[ start..] )?;
+				Some( ( Token::CharEscape { value, span: start..start + length }, start + length ) )
+			},
+			Lexer::Literal( dialect ) => {
+				let ( length, escape ) = literal::escape( &s[ start..], dialect );
+				let span = start..start + length;
+				let token = match escape {
+					Escape::Char( c ) => Token::CharEscape { value: Some( c ), span },
+					Escape::Invalid => Token::CharEscape { value: None, span },
+					Escape::Byte( byte ) => Token::ByteEscape { byte, span },
+					Escape::Continuation => Token::LineContinuation { span },
+					Escape::Unknown => return None,
+				};
+				Some( ( token, start + length ) )
+			},
+			Lexer::Utf7 { imap } => {
+				let ( value, length ) = if bytes.get( start + 1 ) == Some( &b'-' ) {
+					( s[ start..start + 1 ].into( ), 1 )
+				} else {
+					utf7::shifted_run( &bytes[ start + 1..], imap )
+				};
+				let end = start + 1 + length;
+				Some( ( Token::ShiftedRun { value, span: start..end }, end ) )
+			},
+			Lexer::Xml { ref declared, .. } => {
+				let body = &s[ start + 1..];
+				if body.starts_with( '#' ) {
+					return match reference( body )? {
+						( Reference::Numeric( codepoint ), length ) => Some( ( Token::NumericRef { codepoint, span: start..start + 1 + length }, start + 1 + length ) ),
+						_ => None,
+					};
+				}
+				let length = xml::name_length( body );
+				if length == 0 || body.as_bytes( ).get( length ) != Some( &b';' ) {
+					return None;
+				}
+				let name = &body[ ..length ];
+				let span = start..start + length + 2;
+				let token = if let Some( &( _, value ) ) = PREDEFINED.iter( ).find( |&&( predefined, _ )| predefined == name ) {
+					Token::NamedEntity { name, value, span: span.clone( ) }
+				} else if let Some( replacement ) = declared.get( name ) {
+					Token::DeclaredEntity { name, replacement: replacement.clone( ), span: span.clone( ) }
+				} else {
+					Token::UnknownEntity { name, span: span.clone( ) }
+				};
+				Some( ( token, span.end ) )
+			},
+		}
+	}
+}
+
+impl<'a> Iterator for Tokens<'a> {
+	type Item = Token<'a>;
+
+	fn next( &mut self ) -> Option<Token<'a>> {
+		if let Some( token ) = self.pending.take( ) {
+			return Some( token );
+		}
+
+		let s = self.s;
+		let mut from = self.at;
+		while let Some( start ) = self.next_escape( from ) {
+			match self.escape( start ) {
+				Some( ( token, end ) ) => {
+					let literal = &s[ self.at..start ];
+					self.at = end;
+					if literal.is_empty( ) {
+						return Some( token );
+					}
+					self.pending = Some( token );
+					return Some( Token::Literal( literal ) );
+				},
+				None => from = start + 1,
+			}
+		}
+
+		if self.at < s.len( ) {
+			let literal = &s[ self.at..];
+			self.at = s.len( );
+			return Some( Token::Literal( literal ) );
+		}
+		None
+	}
+}
+
+
+#[cfg( test )]
+mod tests {
+
+	use super::*;
+	use alloc::vec::Vec;
+
+	#[test]
+	fn test_entity_tokens( ) {
+		let tokens: Vec<Token> = entity_tokens( "&#38;&#x2665;&#99999999999;& &amp;amp;", EntitySet::Xml ).collect( );
+		assert_eq!( tokens, vec![
+			Token::NumericRef { codepoint: 38, span: 0..5 },
+			Token::NumericRef { codepoint: 0x2665, span: 5..13 },
+			Token::NumericRef { codepoint: u32::MAX, span: 13..27 },
+			Token::Literal( "& " ),
+			Token::NamedEntity { name: "amp", value: "&", span: 29..34 },
+			Token::Literal( "amp;" ),
+		] );
+		assert_eq!( entity_tokens( "", EntitySet::Xml ).count( ),		0 );
+		assert_eq!( entity_tokens( "&#X26;", EntitySet::Xml ).collect::<Vec<_>>( ),	vec![ Token::Literal( "&#X26;" ) ] );
+	}
+
+	#[test]
+	fn test_escape_tokens( ) {
+		let tokens: Vec<Token> = percent_tokens( "%%41%4" ).collect( );
+		assert_eq!( tokens,	vec![ Token::Literal( "%" ), Token::PercentTriplet { byte: b'A', span: 1..4 }, Token::Literal( "%4" ) ] );
+
+		let tokens: Vec<Token> = quoted_printable_tokens( "a=\nb=3D=" ).collect( );
+		assert_eq!( tokens,	vec![ Token::Literal( "a" ), Token::SoftLineBreak { span: 1..3 }, Token::Literal( "b" ), Token::QuotedByte { byte: b'=', span: 4..7 }, Token::Literal( "=" ) ] );
+
+		let tokens: Vec<Token> = rfc2047_tokens( "=?utf-8?B?dGVzdA==?= =?x?z?y?= =?broken" ).collect( );
+		assert_eq!( tokens,	vec![
+			Token::EncodedWord { charset: "utf-8", encoding: "B", bytes: b"test"[ ..].into( ), span: 0..20 },
+			Token::Literal( " " ),
+			Token::EncodedWord { charset: "x", encoding: "z", bytes: b"y"[ ..].into( ), span: 21..30 },
+			Token::Literal( " =?broken" ),
+		] );
+	}
+
+	#[test]
+	fn test_backslash_tokens( ) {
+		let tokens: Vec<Token> = json_tokens( r"\ud83d\ude00\ud83d!\q" ).collect( );
+		assert_eq!( tokens,	vec![
+			Token::CharEscape { value: Some( '😀' ), span: 0..12 },
+			Token::CharEscape { value: None, span: 12..18 },
+			Token::Literal( r"!\q" ),
+		] );
+
+		let tokens: Vec<Token> = literal_tokens( "a\\\n    b\\u{110000}\\\\", Dialect::Rust ).collect( );
+		assert_eq!( tokens,	vec![
+			Token::Literal( "a" ),
+			Token::LineContinuation { span: 1..7 },
+			Token::Literal( "b" ),
+			Token::CharEscape { value: None, span: 8..18 },
+			Token::CharEscape { value: Some( '\\' ), span: 18..20 },
+		] );
+
+		let tokens: Vec<Token> = literal_tokens( r"\N{CJK UNIFIED IDEOGRAPH-4E2D}\N{NO SUCH}\N{", Dialect::Python ).collect( );
+		assert_eq!( tokens,	vec![
+			Token::CharEscape { value: Some( '中' ), span: 0..30 },
+			Token::CharEscape { value: None, span: 30..41 },
+			Token::Literal( r"\N{" ),
+		] );
+	}
+
+	#[test]
+	fn test_utf7_tokens( ) {
+		let tokens: Vec<Token> = utf7_tokens( "1 + 2 +AGE" ).collect( );
+		assert_eq!( tokens,	vec![
+			Token::Literal( "1 " ),
+			Token::ShiftedRun { value: "".into( ), span: 2..3 },
+			Token::Literal( " 2 " ),
+			Token::ShiftedRun { value: "a".into( ), span: 6..10 },
+		] );
+		let tokens: Vec<Token> = imap_utf7_tokens( "&-+AGE" ).collect( );
+		assert_eq!( tokens,	vec![ Token::ShiftedRun { value: "&".into( ), span: 0..2 }, Token::Literal( "+AGE" ) ] );
+	}
+
+	#[test]
+	fn test_xml_tokens( ) {
+		let document = "<!DOCTYPE d [ <!ENTITY a.b 'x'> ]><d>&a.b;&nbsp;<!-- &lt; --><![CDATA[&gt;]]>&#x26;&apos;</d>";
+		let tokens: Vec<Token> = xml_tokens( document ).collect( );
+		assert_eq!( tokens,	vec![
+			Token::Literal( "<!DOCTYPE d [ <!ENTITY a.b 'x'> ]><d>" ),
+			Token::DeclaredEntity { name: "a.b", replacement: "x".into( ), span: 37..42 },
+			Token::UnknownEntity { name: "nbsp", span: 42..48 },
+			Token::Literal( "<!-- &lt; --><![CDATA[&gt;]]>" ),
+			Token::NumericRef { codepoint: 0x26, span: 77..83 },
+			Token::NamedEntity { name: "apos", value: "'", span: 83..89 },
+			Token::Literal( "</d>" ),
+		] );
+		assert_eq!( xml_tokens( "a & b &#; <c>" ).collect::<Vec<_>>( ),	vec![ Token::Literal( "a & b &#; <c>" ) ] );
+
+		// A DOCTYPE outside the prolog is no DOCTYPE, and hides nothing before it
+		let tokens: Vec<Token> = xml_tokens( "&amp; <!-- <!DOCTYPE x> --> &amp;" ).collect( );
+		assert_eq!( tokens,	vec![
+			Token::NamedEntity { name: "amp", value: "&", span: 0..5 },
+			Token::Literal( " <!-- <!DOCTYPE x> --> " ),
+			Token::NamedEntity { name: "amp", value: "&", span: 28..33 },
+		] );
+	}
+}
@@ -0,0 +1,238 @@
+// UTF-7, and the modified UTF-7 IMAP uses for mailbox names
+// See: UTF-7 A Mail-Safe Transformation Format of Unicode https://tools.ietf.org/html/rfc2152
+// See also: IMAP4rev1 Mailbox International Naming Convention https://tools.ietf.org/html/rfc3501#section-5.1.3
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The six bits a (possibly modified) base64 symbol stands for
+fn sextet( byte: u8, imap: bool ) -> Option<u32> {
+	let value = match byte {
+		b'A' ..= b'Z' => byte - b'A',
+		b'a' ..= b'z' => byte - b'a' + 26,
+		b'0' ..= b'9' => byte - b'0' + 52,
+		b'+' => 62,
+		b'/' if !imap => 63,
+		b',' if imap => 63,
+		_ => return None
+	};
+	Some( u32::from( value ) )
+}
+
+fn symbol( value: u32, imap: bool ) -> char {
+	static ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+	match value {
+		63 if imap => ',',
+		_ => ALPHABET[ value as usize ] as char,
+	}
+}
+
+/// Decodes UTF-7 held in bytes, shared by the charset layer and the public functions
+pub(crate) fn decode_utf7_bytes( s: &[u8], imap: bool ) -> String {
+
+	let shift = if imap { b'&' } else { b'+' };
+	let mut allo = String::with_capacity( s.len( ) );
+	let mut i = 0;
+
+	while i < s.len( ) {
+		let byte = s[ i ];
+		i += 1;
+
+		if byte != shift {
+			// UTF-7 is 7 bit; anything else has been damaged along the way
+			allo.push( if byte < 0x80 { byte as char } else { '\u{FFFD}' } );
+			continue;
+		}
+		if s.get( i ) == Some( &b'-' ) {
+			allo.push( shift as char );
+			i += 1;
+			continue;
+		}
+
+		let ( text, length ) = shifted_run( &s[ i..], imap );
+		allo.push_str( &text );
+		i += length;
+	}
+
+	allo
+}
+
+/// Reads the base64 UTF-16 that follows a shift character, with how much of `s` it took
+pub(crate) fn shifted_run( s: &[u8], imap: bool ) -> ( String, usize ) {
+
+	let mut accumulated = 0u32;
+	let mut bits = 0u32;
+	let mut units: Vec<u16> = Vec::new( );
+	let mut i = 0;
+	while let Some( value ) = s.get( i ).and_then( |&byte| sextet( byte, imap ) ) {
+		accumulated = accumulated << 6 | value;
+		bits += 6;
+		if bits >= 16 {
+			bits -= 16;
+			units.push( ( accumulated >> bits ) as u16 );
+			accumulated &= ( 1 << bits ) - 1;
+		}
+		i += 1;
+	}
+	// the - that closes a shifted run is absorbed, any other character is literal
+	if s.get( i ) == Some( &b'-' ) {
+		i += 1;
+	}
+
+	( char::decode_utf16( units ).map( |c| c.unwrap_or( '\u{FFFD}' ) ).collect( ), i )
+}
+
+/// Writes a run of characters as UTF-16 in (possibly modified) base64, opened with the shift character and closed with `-`
+fn shifted( allo: &mut String, run: &str, imap: bool ) {
+
+	allo.push( if imap { '&' } else { '+' } );
+
+	let mut accumulated = 0u32;
+	let mut bits = 0u32;
+	for unit in run.encode_utf16( ) {
+		accumulated = accumulated << 16 | u32::from( unit );
+		bits += 16;
+		while bits >= 6 {
+			bits -= 6;
+			allo.push( symbol( accumulated >> bits & 0x3F, imap ) );
+		}
+		accumulated &= ( 1 << bits ) - 1;
+	}
+	if bits > 0 {
+		allo.push( symbol( accumulated << ( 6 - bits ) & 0x3F, imap ) );
+	}
+	allo.push( '-' );
+}
+
+fn encode_with<'a>( s: &'a str, imap: bool, direct: &dyn Fn( char ) -> bool ) -> Cow<'a, str> {
+
+	let shift = if imap { '&' } else { '+' };
+
+	if s.chars( ).all( |c| c != shift && direct( c ) ) {
+		return s.into( );
+	}
+
+	let mut allo = String::with_capacity( s.len( ) * 2 );
+	let mut rest = s;
+	while let Some( c ) = rest.chars( ).next( ) {
+		if c == shift {
+			allo.push( shift );
+			allo.push( '-' );
+			rest = &rest[ 1..];
+		} else if direct( c ) {
+			allo.push( c );
+			rest = &rest[ c.len_utf8( )..];
+		} else {
+			let end = rest.find( |c: char| c == shift || direct( c ) ).unwrap_or( rest.len( ) );
+			shifted( &mut allo, &rest[ ..end ], imap );
+			rest = &rest[ end..];
+		}
+	}
+
+	allo.into( )
+}
+
+/// Decodes UTF-7 into its UTF-8 form
+///
+/// Unpaired surrogates, and any byte outside 7 bit ASCII, become U+FFFD.
+///
+/// # Examples
+///
+/// ```
+/// use mung::decode_utf7;
+/// assert_eq!( decode_utf7( "Hi Mom -+Jjo--!" ), "Hi Mom -☺-!" );
+/// assert_eq!( decode_utf7( "A+ImIDkQ." ), "A≢Α." );
+/// ```
+pub fn decode_utf7<'a>( s: &'a str ) -> Cow<'a, str> {
+	if s.contains( '+' ) || !s.is_ascii( ) {
+		decode_utf7_bytes( s.as_bytes( ), false ).into( )
+	} else {
+		s.into( )
+	}
+}
+
+/// Encodes text as UTF-7
+///
+/// Only RFC 2152’s Set D and white space are written directly, as those
+/// pass through every mail gateway; everything else is shifted into base64.
+///
+/// # Examples
+///
+/// ```
+/// use mung::encode_utf7;
+/// assert_eq!( encode_utf7( "Hi Mom -☺-!" ), "Hi Mom -+Jjo--+ACE-" );
+/// assert_eq!( encode_utf7( "1 + 1" ), "1 +- 1" );
+/// ```
+pub fn encode_utf7<'a>( s: &'a str ) -> Cow<'a, str> {
+	encode_with( s, false, &|c: char| {
+		c.is_ascii_alphanumeric( ) || "'(),-./:? \t\r\n".contains( c )
+	} )
+}
+
+/// Decodes an IMAP mailbox name from modified UTF-7 into its UTF-8 form
+///
+/// # Examples
+///
+/// ```
+/// use mung::decode_imap_utf7;
+/// assert_eq!( decode_imap_utf7( "&AMk-t&AOk-" ), "Été" );
+/// ```
+pub fn decode_imap_utf7<'a>( s: &'a str ) -> Cow<'a, str> {
+	if s.contains( '&' ) || !s.is_ascii( ) {
+		decode_utf7_bytes( s.as_bytes( ), true ).into( )
+	} else {
+		s.into( )
+	}
+}
+
+/// Encodes an IMAP mailbox name as modified UTF-7
+///
+/// # Examples
+///
+/// ```
+/// use mung::encode_imap_utf7;
+/// assert_eq!( encode_imap_utf7( "Été & Hiver" ), "&AMk-t&AOk- &- Hiver" );
+/// ```
+pub fn encode_imap_utf7<'a>( s: &'a str ) -> Cow<'a, str> {
+	encode_with( s, true, &|c: char| ( ' ' ..= '~' ).contains( &c ) )
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_utf7( ) {
+
+		// Examples from RFC 2152
+		assert_eq!( decode_utf7( "A+ImIDkQ." ),						"A≢Α." );
+		assert_eq!( decode_utf7( "Hi Mom -+Jjo--!" ),				"Hi Mom -☺-!" );
+		assert_eq!( decode_utf7( "+ZeVnLIqe-" ),					"日本語" );
+		assert_eq!( decode_utf7( "Item 3 is +AKM-1." ),				"Item 3 is £1." );
+		assert_eq!( decode_utf7( "1 +- 1" ),						"1 + 1" );
+		assert_eq!( decode_utf7( "plain" ),							"plain" );
+		assert_eq!( decode_utf7( "+2D3eAA-" ),						"😀" );
+		assert_eq!( decode_utf7( "+2D0-" ),							"�" );
+
+		for original in &[ "", "plain", "Hi Mom -☺-!", "日本語", "£1 + 1", "😀 & ♥", "tab\tnew\r\nline" ] {
+			assert_eq!( decode_utf7( &encode_utf7( original ) ), *original );
+		}
+	}
+
+	#[test]
+	fn test_imap_utf7( ) {
+
+		// Example from RFC 3501
+		assert_eq!( decode_imap_utf7( "~peter/mail/&U,BTFw-/&ZeVnLIqe-" ),	"~peter/mail/台北/日本語" );
+		assert_eq!( encode_imap_utf7( "~peter/mail/台北/日本語" ),			"~peter/mail/&U,BTFw-/&ZeVnLIqe-" );
+		assert_eq!( decode_imap_utf7( "&AMk-t&AOk-" ),						"Été" );
+		assert_eq!( decode_imap_utf7( "Tom &- Jerry" ),						"Tom & Jerry" );
+		assert_eq!( encode_imap_utf7( "INBOX" ),							"INBOX" );
+
+		for original in &[ "", "Été", "Sent & Received", "😀", "Entwürfe" ] {
+			assert_eq!( decode_imap_utf7( &encode_imap_utf7( original ) ), *original );
+		}
+	}
+}
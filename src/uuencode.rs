@@ -0,0 +1,158 @@
+// uuencode, as found in old mail archives and Usenet dumps
+// See: The Single UNIX Specification, uuencode https://pubs.opengroup.org/onlinepubs/9699919799/utilities/uuencode.html
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use base64::{decode_base64_bytes, Base64};
+use error::DecodeError;
+
+/// A file recovered from a transfer encoding
+#[derive( Clone, Debug, PartialEq, Eq )]
+pub struct Attachment {
+	/// The file name the sender gave
+	pub name: String,
+	/// The Unix permission bits, for encodings that carry them
+	pub mode: Option<u32>,
+	/// What the file holds
+	pub bytes: Vec<u8>,
+}
+
+/// Splits text into lines along with the offset each starts at, without their line endings
+fn lines( s: &str ) -> Vec<( usize, &str )> {
+	let mut offset = 0;
+	s.split( '\n' ).map( |line| {
+		let start = offset;
+		offset += line.len( ) + 1;
+		( start, line.trim_end_matches( '\r' ) )
+	} ).collect( )
+}
+
+/// Reads a `begin` or `begin-base64` line: whether it is base64, its octal mode and the file name
+fn header( line: &str ) -> Option<( bool, u32, String )> {
+	let mut fields = line.splitn( 3, ' ' );
+	let base64 = match fields.next( ) {
+		Some( "begin" ) => false,
+		Some( "begin-base64" ) => true,
+		_ => return None,
+	};
+	let mode = u32::from_str_radix( fields.next( )?, 8 ).ok( )?;
+	let name = fields.next( ).filter( |name| !name.is_empty( ) )?;
+	Some( ( base64, mode, name.to_string( ) ) )
+}
+
+/// Decodes the first uuencoded file found in `s`
+///
+/// Text before the `begin` line is skipped, so a whole message can be passed
+/// in; so are lines that start with `begin` but have no mode and name. The POSIX `begin-base64` form is understood too. Mail systems that
+/// strip trailing spaces from lines are allowed for.
+///
+/// # Examples
+///
+/// ```
+/// use mung::decode_uuencode;
+/// let file = decode_uuencode( "begin 644 cat.txt\n#0V%T\n`\nend\n" ).unwrap( );
+/// assert_eq!( file.name, "cat.txt" );
+/// assert_eq!( file.mode, Some( 0o644 ) );
+/// assert_eq!( file.bytes, b"Cat" );
+/// ```
+pub fn decode_uuencode( s: &str ) -> Result<Attachment, DecodeError> {
+
+	let lines = lines( s );
+	// Prose such as "begin here" is passed over for the next line that is a header
+	let ( begin, base64, mode, name ) = lines.iter( ).enumerate( )
+		.find_map( |( i, &( _, line ) )| header( line ).map( |( base64, mode, name )| ( i, base64, mode, name ) ) )
+		.ok_or( DecodeError::MissingLine { line: "begin" } )?;
+	let ( begin_at, begin_line ) = lines[ begin ];
+
+	if base64 {
+		let end = lines[ begin + 1..].iter( ).position( |&( _, line )| line == "====" )
+			.ok_or( DecodeError::MissingLine { line: "====" } )?;
+		let body_at = begin_at + begin_line.len( ) + 1;
+		let body_end = lines[ begin + 1 + end ].0;
+		let bytes = decode_base64_bytes( &s.as_bytes( )[ body_at..body_end ], Base64::Mime ).map_err( |error| match error {
+			DecodeError::InvalidByte { offset, byte } => DecodeError::InvalidByte { offset: body_at + offset, byte },
+			error => error,
+		} )?;
+		return Ok( Attachment { name, mode: Some( mode ), bytes } );
+	}
+
+	let mut bytes = Vec::new( );
+	let mut ended = false;
+	for &( line_at, line ) in &lines[ begin + 1..] {
+
+		if line == "end" {
+			ended = true;
+			break;
+		}
+		let line = line.as_bytes( );
+		let length = match line.first( ) {
+			Some( &byte ) => ( byte.wrapping_sub( b' ' ) & 0x3F ) as usize,
+			None => continue,
+		};
+		if length == 0 {
+			continue;
+		}
+
+		let mut group = [ 0u8; 4 ];
+		let mut written = 0;
+		for chunk in 0..length.div_ceil( 3 ) {
+			for ( i, value ) in group.iter_mut( ).enumerate( ) {
+				let offset = 1 + chunk * 4 + i;
+				// trailing spaces may have been stripped on the way
+				let byte = line.get( offset ).cloned( ).unwrap_or( b' ' );
+				if !( b' ' ..= b'`' ).contains( &byte ) {
+					return Err( DecodeError::InvalidByte { offset: line_at + offset, byte } );
+				}
+				*value = ( byte - b' ' ) & 0x3F;
+			}
+			let triple = [ group[ 0 ] << 2 | group[ 1 ] >> 4, group[ 1 ] << 4 | group[ 2 ] >> 2, group[ 2 ] << 6 | group[ 3 ] ];
+			let wanted = ( length - written ).min( 3 );
+			bytes.extend_from_slice( &triple[ ..wanted ] );
+			written += wanted;
+		}
+	}
+
+	if !ended {
+		return Err( DecodeError::MissingLine { line: "end" } );
+	}
+
+	Ok( Attachment { name, mode: Some( mode ), bytes } )
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_decode_uuencode( ) {
+
+		let message = "From: someone\n\nHere it is\n\nbegin 644 cat.txt\nF0V%T /\\@:&5L;&\\@=V]R;&0L('1H:7,@:7,@=75E;F-O9&5D(0H \n`\nend\n";
+		let expected = Attachment {
+			name: "cat.txt".to_string( ),
+			mode: Some( 0o644 ),
+			bytes: b"Cat\x00\xff hello world, this is uuencoded!\n".to_vec( ),
+		};
+		assert_eq!( decode_uuencode( message ),								Ok( expected.clone( ) ) );
+		// with the trailing space stripped, and CRLF line endings
+		assert_eq!( decode_uuencode( &message.replace( "(0H \n", "(0H\n" ) ),	Ok( expected.clone( ) ) );
+		assert_eq!( decode_uuencode( &message.replace( "\n", "\r\n" ) ),		Ok( expected ) );
+
+		assert_eq!( decode_uuencode( "begin 755 my file\n#0V%T\n`\nend" ).map( |file| ( file.name, file.mode ) ),
+																			Ok( ( "my file".to_string( ), Some( 0o755 ) ) ) );
+		assert_eq!( decode_uuencode( "begin-base64 600 secret\nQ2F0\n====\n" ).map( |file| file.bytes ),	Ok( b"Cat".to_vec( ) ) );
+		assert_eq!( decode_uuencode( "begin here, then\nbegin 9 x\nbegin 644\nbegin 644 cat.txt\n#0V%T\n`\nend" ).map( |file| file.bytes ),	Ok( b"Cat".to_vec( ) ) );
+	}
+
+	#[test]
+	fn test_decode_uuencode_failure( ) {
+
+		assert_eq!( decode_uuencode( "no attachment here" ),				Err( DecodeError::MissingLine { line: "begin" } ) );
+		assert_eq!( decode_uuencode( "begin here\nand end\n" ),			Err( DecodeError::MissingLine { line: "begin" } ) );
+		assert_eq!( decode_uuencode( "begin 644 cat.txt\n#0V%T\n" ),		Err( DecodeError::MissingLine { line: "end" } ) );
+		assert_eq!( decode_uuencode( "begin 644 cat.txt\n#0v%T\n`\nend" ),	Err( DecodeError::InvalidByte { offset: 20, byte: b'v' } ) );
+		assert_eq!( decode_uuencode( "begin-base64 644 cat.txt\nQ2F0\n" ),	Err( DecodeError::MissingLine { line: "====" } ) );
+		assert_eq!( decode_uuencode( "begin-base64 644 c\nQ2!0\n====" ),	Err( DecodeError::InvalidByte { offset: 21, byte: b'!' } ) );
+	}
+}
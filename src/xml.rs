@@ -0,0 +1,406 @@
+// XML documents, which may declare their own entities in a DOCTYPE
+// See: Extensible Markup Language (XML) 1.0 https://www.w3.org/TR/xml/#sec-entity-decl
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use error::DecodeError;
+use {suggest_entity, EntitySet};
+
+/// How far `decode_xml` lets declared entities expand
+///
+/// A few nested declarations can expand into gigabytes ("billion laughs"),
+/// so both the nesting depth and the size of the output are bounded.
+#[derive( Clone, Copy, Debug, PartialEq, Eq )]
+pub struct XmlOptions {
+	/// How deeply entities may refer to other entities
+	pub max_depth: usize,
+	/// The most bytes of text the decoded document may hold
+	pub max_output: usize,
+	/// Fail on references to entities that were never declared, rather than keep them;
+	/// the error suggests the declared name that was probably meant
+	pub strict: bool,
+}
+
+impl Default for XmlOptions {
+	fn default( ) -> XmlOptions {
+		XmlOptions { max_depth: 16, max_output: 1 << 20, strict: false }
+	}
+}
+
+/// Entities every XML document has, without declaring them
+pub(crate) static PREDEFINED: &[( &str, &str )] = &[ ( "amp", "&" ), ( "lt", "<" ), ( "gt", ">" ), ( "quot", "\"" ), ( "apos", "'" ) ];
+
+/// Finds the `>` that closes a declaration, stepping over quoted literals
+fn declaration_end( s: &str ) -> Option<usize> {
+	let mut quote = None;
+	for ( i, byte ) in s.bytes( ).enumerate( ) {
+		match ( quote, byte ) {
+			( None, b'"' ) | ( None, b'\'' ) => quote = Some( byte ),
+			( Some( open ), _ ) if open == byte => quote = None,
+			( None, b'>' ) => return Some( i ),
+			_ => { }
+		}
+	}
+	None
+}
+
+fn is_name_byte( byte: u8 ) -> bool {
+	byte.is_ascii_alphanumeric( ) || byte == b'_' || byte == b':' || byte == b'.' || byte == b'-' || !byte.is_ascii( )
+}
+
+/// Length of the XML name at the start of `s`, if there is one
+pub(crate) fn name_length( s: &str ) -> usize {
+	match s.bytes( ).next( ) {
+		Some( first ) if first.is_ascii_alphabetic( ) || first == b'_' || first == b':' || !first.is_ascii( ) =>
+			s.bytes( ).take_while( |&byte| is_name_byte( byte ) ).count( ),
+		_ => 0,
+	}
+}
+
+/// Reads a `&#…;` character reference from the start of `s`, returning it and its length
+fn char_reference( s: &str ) -> Option<( char, usize )> {
+	let ( skip, radix ) = if s.starts_with( "&#x" ) {
+		( 3, 16 )
+	} else if s.starts_with( "&#" ) {
+		( 2, 10 )
+	} else {
+		return None;
+	};
+	// Only the digits are read, so a missing ; is found without scanning the rest of the input
+	let length = s[ skip..].bytes( ).take_while( |byte| ( *byte as char ).is_digit( radix ) ).count( );
+	if length == 0 || s.as_bytes( ).get( skip + length ) != Some( &b';' ) {
+		return None;
+	}
+	let code = u32::from_str_radix( &s[ skip..skip + length ], radix ).ok( )?;
+	Some( ( char::from_u32( code ).unwrap_or( '\u{FFFD}' ), skip + length + 1 ) )
+}
+
+/// Reads the general entity declared by `<!ENTITY …>`, when it has a literal value
+///
+/// Character references in the value are replaced now, as XML requires; entity
+/// references wait until the entity is used. Parameter entities and external
+/// entities, which would need fetching, are not read. The `CDATA` and `SDATA`
+/// keywords of SGML entity sets are understood.
+fn entity_declaration( declaration: &str ) -> Option<( String, String )> {
+	let rest = declaration[ "<!ENTITY".len( )..].trim_start( );
+	let length = name_length( rest );
+	if length == 0 {
+		return None;
+	}
+	let name = &rest[ ..length ];
+	let mut rest = rest[ length..].trim_start( );
+
+	// SGML entity sets mark their values as character or system data
+	let mut system_data = false;
+	if rest.starts_with( "CDATA" ) || rest.starts_with( "SDATA" ) {
+		system_data = rest.starts_with( 'S' );
+		rest = rest[ 5..].trim_start( );
+	}
+	let quote = rest.chars( ).next( )?;
+	if quote != '"' && quote != '\'' {
+		return None;
+	}
+	let literal = &rest[ 1..1 + rest[ 1..].find( quote )? ];
+	if system_data && literal.starts_with( '[' ) {
+		// Such as "[frac12]", which names a glyph rather than giving it
+		return None;
+	}
+
+	let mut value = String::with_capacity( literal.len( ) );
+	let mut remaining = literal;
+	while let Some( found ) = remaining.find( "&#" ) {
+		value.push_str( &remaining[ ..found ] );
+		remaining = &remaining[ found..];
+		match char_reference( remaining ) {
+			Some( ( c, length ) ) => {
+				value.push( c );
+				remaining = &remaining[ length..];
+			},
+			None => {
+				value.push( '&' );
+				remaining = &remaining[ 1..];
+			}
+		}
+	}
+	value.push_str( remaining );
+
+	Some( ( name.to_string( ), value ) )
+}
+
+/// Reads the entity declarations of a DTD, or of an internal subset without its brackets
+///
+/// Returns the declarations, first one winning as XML requires, and where the
+/// subset ended: at the closing `]`, or at the end of `s`.
+pub(crate) fn entity_declarations( s: &str ) -> ( BTreeMap<String, String>, usize ) {
+	let mut entities = BTreeMap::new( );
+	let mut at = 0;
+
+	while at < s.len( ) {
+		let rest = &s[ at..];
+		if rest.starts_with( ']' ) {
+			break;
+		} else if rest.starts_with( "<!--" ) {
+			at += rest.find( "-->" ).map_or( rest.len( ), |end| end + 3 );
+		} else if rest.starts_with( "<?" ) {
+			at += rest.find( "?>" ).map_or( rest.len( ), |end| end + 2 );
+		} else if rest.starts_with( "<![" ) {
+			// A marked section of an external DTD; read what it holds
+			at += rest.match_indices( '[' ).nth( 1 ).map_or( rest.len( ), |( open, _ )| open + 1 );
+		} else if rest.starts_with( '<' ) {
+			let end = declaration_end( rest ).map_or( rest.len( ), |end| end + 1 );
+			if rest.starts_with( "<!ENTITY" ) {
+				if let Some( ( name, value ) ) = entity_declaration( &rest[ ..end ] ) {
+					entities.entry( name ).or_insert( value );
+				}
+			}
+			at += end;
+		} else {
+			at += rest.chars( ).next( ).map_or( 1, |c| c.len_utf8( ) );
+		}
+	}
+
+	( entities, at )
+}
+
+/// Reads the entities a document's DOCTYPE declares, with where the DOCTYPE ends and its body begins
+///
+/// Only a DOCTYPE in the prolog counts: before it may come white space, the
+/// XML declaration, processing instructions and comments, but nothing else.
+pub(crate) fn doctype( s: &str ) -> ( BTreeMap<String, String>, usize ) {
+	let mut doctype = 0;
+	loop {
+		let rest = &s[ doctype..];
+		let blank = rest.len( ) - rest.trim_start_matches( [ ' ', '\t', '\r', '\n', '\u{FEFF}' ] ).len( );
+		doctype += blank;
+		let rest = &s[ doctype..];
+		if rest.starts_with( "<!DOCTYPE" ) {
+			break;
+		}
+		let skipped = if rest.starts_with( "<!--" ) {
+			rest.find( "-->" ).map( |end| end + 3 )
+		} else if rest.starts_with( "<?" ) {
+			rest.find( "?>" ).map( |end| end + 2 )
+		} else {
+			None
+		};
+		match skipped {
+			Some( length ) => doctype += length,
+			// An element, text or CDATA: the prolog is over, with no DOCTYPE in it
+			None => return ( BTreeMap::new( ), 0 ),
+		}
+	}
+	let open = declaration_end( &s[ doctype..] ).map( |end| doctype + end );
+	let subset = s[ doctype..].find( '[' ).map( |start| doctype + start + 1 );
+	match ( subset, open ) {
+		( Some( subset ), Some( open ) ) if subset < open => {
+			let ( declared, end ) = entity_declarations( &s[ subset..] );
+			let after = subset + end;
+			( declared, s[ after..].find( '>' ).map_or( s.len( ), |close| after + close + 1 ) )
+		},
+		( _, Some( open ) ) => ( BTreeMap::new( ), open + 1 ),
+		_ => ( BTreeMap::new( ), s.len( ) ),
+	}
+}
+
+/// The length of the comment or CDATA section that starts `s`, whose text holds no references
+pub(crate) fn verbatim_length( s: &str ) -> Option<usize> {
+	if s.starts_with( "<!--" ) {
+		Some( s.find( "-->" ).map_or( s.len( ), |end| end + 3 ) )
+	} else if s.starts_with( "<![CDATA[" ) {
+		Some( s.find( "]]>" ).map_or( s.len( ), |end| end + 3 ) )
+	} else {
+		None
+	}
+}
+
+/// Replaces entity references in replacement text, recursively
+struct Expander {
+	entities: BTreeMap<String, String>,
+	options: XmlOptions,
+}
+
+impl Expander {
+	/// Expands the reference at the start of `s` into `allo`, returning how much of `s` it took
+	fn reference( &self, s: &str, depth: usize, offset: usize, allo: &mut String ) -> Result<usize, DecodeError> {
+		if let Some( ( c, length ) ) = char_reference( s ) {
+			allo.push( c );
+			return Ok( length );
+		}
+
+		let length = name_length( &s[ 1..] );
+		if length == 0 || s.as_bytes( ).get( length + 1 ) != Some( &b';' ) {
+			// Not a reference, just a stray ampersand
+			allo.push( '&' );
+			return Ok( 1 );
+		}
+		let name = &s[ 1..length + 1 ];
+
+		if let Some( &( _, value ) ) = PREDEFINED.iter( ).find( |&&( predefined, _ )| predefined == name ) {
+			allo.push_str( value );
+		} else if let Some( value ) = self.entities.get( name ) {
+			if depth >= self.options.max_depth {
+				return Err( DecodeError::ExpansionLimit { offset } );
+			}
+			self.expand( value, depth + 1, offset, allo )?;
+		} else if self.options.strict {
+			return Err( self.undeclared( name, offset ) );
+		} else {
+			allo.push_str( &s[ ..length + 2 ] );
+		}
+
+		if allo.len( ) > self.options.max_output {
+			return Err( DecodeError::ExpansionLimit { offset } );
+		}
+		Ok( length + 2 )
+	}
+
+	/// The error for a reference at `offset` to `name`, suggesting the declared name it may have meant
+	fn undeclared( &self, name: &str, offset: usize ) -> DecodeError {
+		let known: Vec<( &str, &str )> = PREDEFINED.iter( ).cloned( )
+			.chain( self.entities.iter( ).map( |( name, value )| ( name.as_str( ), value.as_str( ) ) ) )
+			.collect( );
+		let suggestion = suggest_entity( name, EntitySet::Custom( &known ) ).map( String::from );
+		DecodeError::UndeclaredEntity { offset, name: name.to_string( ), suggestion }
+	}
+
+	/// Expands the replacement text of an entity used at `offset`
+	fn expand( &self, s: &str, depth: usize, offset: usize, allo: &mut String ) -> Result<( ), DecodeError> {
+		let mut rest = s;
+		while let Some( found ) = rest.find( '&' ) {
+			allo.push_str( &rest[ ..found ] );
+			let length = self.reference( &rest[ found..], depth, offset, allo )?;
+			rest = &rest[ found + length..];
+		}
+		allo.push_str( rest );
+		Ok( ( ) )
+	}
+}
+
+/// Decodes the entity and character references of an XML document
+///
+/// Entities declared in the internal subset of its DOCTYPE, such as
+/// `<!ENTITY corp "ACME &amp; Sons">`, are expanded wherever they are used,
+/// along with the five XML predefines. HTML names such as `&nbsp;` mean
+/// nothing to XML, so unless declared they are kept as found, or rejected with
+/// `options.strict`. The DOCTYPE, comments and CDATA sections are copied
+/// untouched. External entities are never fetched, and are treated as
+/// undeclared.
+///
+/// # Examples
+///
+/// ```
+/// use mung::{decode_xml, XmlOptions};
+/// let document = r#"<!DOCTYPE memo [ <!ENTITY corp "ACME &amp; Sons"> ]><to>&corp;</to>"#;
+/// let decoded = decode_xml( document, XmlOptions::default( ) ).unwrap( );
+/// assert!( decoded.ends_with( "<to>ACME & Sons</to>" ) );
+/// ```
+pub fn decode_xml<'a>( s: &'a str, options: XmlOptions ) -> Result<Cow<'a, str>, DecodeError> {
+
+	if !s.contains( '&' ) {
+		return Ok( s.into( ) );
+	}
+
+	let ( entities, body ) = doctype( s );
+	let expander = Expander { entities, options };
+	let mut allo = String::with_capacity( s.len( ) );
+	allo.push_str( &s[ ..body ] );
+
+	let mut at = body;
+	while let Some( found ) = s[ at..].find( &[ '&', '<' ][ ..] ) {
+		allo.push_str( &s[ at..at + found ] );
+		at += found;
+		let rest = &s[ at..];
+
+		let verbatim = match verbatim_length( rest ) {
+			None if rest.starts_with( '<' ) => Some( 1 ),
+			verbatim => verbatim,
+		};
+
+		at += match verbatim {
+			Some( length ) => {
+				allo.push_str( &rest[ ..length ] );
+				length
+			},
+			None => expander.reference( rest, 0, at, &mut allo )?,
+		};
+	}
+	allo.push_str( &s[ at..] );
+
+	Ok( allo.into( ) )
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	fn decode( s: &str ) -> String {
+		decode_xml( s, XmlOptions::default( ) ).unwrap( ).into_owned( )
+	}
+
+	#[test]
+	fn test_decode_xml( ) {
+
+		assert_eq!( decode( "" ),										"" );
+		assert_eq!( decode( "<a>plain</a>" ),							"<a>plain</a>" );
+		assert_eq!( decode( "&lt;&amp;&gt;&quot;&apos;" ),				"<&>\"'" );
+		assert_eq!( decode( "&#38;#38; &#x2665;" ),						"&#38; ♥" );
+		assert_eq!( decode( "&nbsp; & &;" ),							"&nbsp; & &;" );
+		assert_eq!( decode( "<!-- &amp; --><![CDATA[&amp;]]>&amp;" ),	"<!-- &amp; --><![CDATA[&amp;]]>&" );
+
+		// Each &# looks no further than its digits for a ;
+		let unended = "&#".repeat( 100_000 );
+		assert_eq!( decode( &unended ),	unended );
+	}
+
+	#[test]
+	fn test_decode_xml_doctype( ) {
+
+		let doctype = "<?xml version=\"1.0\"?>\n<!DOCTYPE doc [\n\t<!-- house style -->\n\t<!ENTITY corp \"ACME &amp; Sons\">\n\t<!ENTITY sig '&corp; &#169; 2016'>\n\t<!ENTITY corp \"ignored\">\n\t<!ENTITY % param \"not general\">\n\t<!ENTITY ext SYSTEM \"file:///etc/passwd\">\n\t<!ELEMENT doc (#PCDATA)>\n]>\n";
+
+		assert_eq!( decode( &format!( "{}<doc>&sig;</doc>", doctype ) ),		format!( "{}<doc>ACME & Sons © 2016</doc>", doctype ) );
+		assert_eq!( decode( &format!( "{}<doc>&param;&ext;</doc>", doctype ) ),	format!( "{}<doc>&param;&ext;</doc>", doctype ) );
+
+		// The TODO from the ENTITIES table
+		assert_eq!( decode( "<!DOCTYPE d [<!ENTITY amp \"&#38;#38;\">]>&amp;amp;" ),	"<!DOCTYPE d [<!ENTITY amp \"&#38;#38;\">]>&amp;" );
+		assert_eq!( decode( "<!DOCTYPE d [<!ENTITY and \"&#38;#38;\">]>&and;" ),		"<!DOCTYPE d [<!ENTITY and \"&#38;#38;\">]>&" );
+		assert_eq!( decode( "<!DOCTYPE d SYSTEM \"d.dtd\">&amp;" ),						"<!DOCTYPE d SYSTEM \"d.dtd\">&" );
+
+		// Only a DOCTYPE in the prolog declares anything
+		assert_eq!( decode( "&amp; <![CDATA[<!DOCTYPE x>]]> &amp;" ),							"& <![CDATA[<!DOCTYPE x>]]> &" );
+		assert_eq!( decode( "&amp; <!-- <!DOCTYPE x [ <!ENTITY a \"b\"> ]> --> &a;" ),				"& <!-- <!DOCTYPE x [ <!ENTITY a \"b\"> ]> --> &a;" );
+		assert_eq!( decode( "<doc><!DOCTYPE x [ <!ENTITY a \"b\"> ]>&a;</doc>" ),					"<doc><!DOCTYPE x [ <!ENTITY a \"b\"> ]>&a;</doc>" );
+		assert_eq!( decode( "<!-- <!DOCTYPE> --><?pi?> <!DOCTYPE d [<!ENTITY a \"b\">]>&a;" ),	"<!-- <!DOCTYPE> --><?pi?> <!DOCTYPE d [<!ENTITY a \"b\">]>b" );
+	}
+
+	#[test]
+	fn test_decode_xml_limits( ) {
+
+		let laughs = "<!DOCTYPE lolz [\n<!ENTITY lol \"lol\">\n<!ENTITY lol1 \"&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;\">\n<!ENTITY lol2 \"&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;&lol1;\">\n<!ENTITY lol3 \"&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;\">\n<!ENTITY lol4 \"&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;\">\n<!ENTITY lol5 \"&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;&lol4;\">\n<!ENTITY lol6 \"&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;&lol5;\">\n<!ENTITY lol7 \"&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;&lol6;\">\n<!ENTITY lol8 \"&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;&lol7;\">\n<!ENTITY lol9 \"&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;&lol8;\">\n]>\n<lolz>&lol9;</lolz>";
+		let offset = laughs.find( "&lol9;" ).unwrap( );
+
+		assert_eq!( decode_xml( laughs, XmlOptions::default( ) ),	Err( DecodeError::ExpansionLimit { offset } ) );
+		assert_eq!( decode_xml( laughs, XmlOptions { max_depth: 4, ..XmlOptions::default( ) } ),	Err( DecodeError::ExpansionLimit { offset } ) );
+		assert!( decode_xml( laughs, XmlOptions { max_output: 2_000_000_000, max_depth: 8, strict: false } ).is_err( ) );
+
+		let looping = "<!DOCTYPE d [<!ENTITY a \"&b;\"><!ENTITY b \"&a;\">]>&a;";
+		assert_eq!( decode_xml( looping, XmlOptions::default( ) ),	Err( DecodeError::ExpansionLimit { offset: looping.len( ) - 3 } ) );
+	}
+
+	#[test]
+	fn test_decode_xml_strict( ) {
+
+		let strict = XmlOptions { strict: true, ..XmlOptions::default( ) };
+		assert_eq!( decode_xml( "a &nbsp; b", strict ),			Err( DecodeError::UndeclaredEntity { offset: 2, name: "nbsp".into( ), suggestion: None } ) );
+		let misspelt = decode_xml( "<!DOCTYPE d [<!ENTITY corp \"ACME\">]>&copr; &am;", strict ).unwrap_err( );
+		assert_eq!( misspelt,	DecodeError::UndeclaredEntity { offset: 36, name: "copr".into( ), suggestion: Some( "corp".into( ) ) } );
+		assert_eq!( misspelt.to_string( ),	"undeclared entity &copr; at offset 36, did you mean &corp;?" );
+		assert_eq!( decode_xml( "&am;", strict ).unwrap_err( ).to_string( ),	"undeclared entity &am; at offset 0, did you mean &amp;?" );
+		assert_eq!( decode_xml( "<!DOCTYPE d [<!ENTITY nbsp \"&#160;\">]>&nbsp;", strict ).unwrap( ),	"<!DOCTYPE d [<!ENTITY nbsp \"&#160;\">]>\u{A0}" );
+		assert_eq!( decode_xml( "&amp; &#65; & stray", strict ).unwrap( ),	"& A & stray" );
+		assert_eq!( decode_xml( "&#x+41; &#-65; &#x41", strict ).unwrap( ),	"&#x+41; &#-65; &#x41" );
+	}
+}
@@ -0,0 +1,260 @@
+// yEnc, the 8 bit Usenet binary encoding
+// See: yEnc - Efficient encoding for Usenet and eMail http://www.yenc.org/yenc-draft.1.3.txt
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use error::DecodeError;
+use uuencode::Attachment;
+
+/// CRC-32 as yEnc, zip and Ethernet use it
+fn crc32( bytes: &[u8] ) -> u32 {
+	let mut crc = !0u32;
+	for &byte in bytes {
+		crc ^= u32::from( byte );
+		for _ in 0..8 {
+			crc = if crc & 1 != 0 { crc >> 1 ^ 0xEDB8_8320 } else { crc >> 1 };
+		}
+	}
+	!crc
+}
+
+/// One `=ybegin` … `=yend` block
+struct Part {
+	name: String,
+	size: u64,
+	part: Option<u32>,
+	total: Option<u32>,
+	range: Option<( u64, u64 )>,
+	crc32: Option<u32>,
+	bytes: Vec<u8>,
+}
+
+/// Reads `key=value` out of a header line; `name` always runs to the end of the line
+fn field<'a>( line: &'a [u8], key: &str ) -> Option<&'a str> {
+	let text = ::alloc::str::from_utf8( line ).ok( )?;
+	let name_at = text.find( " name=" ).unwrap_or( text.len( ) );
+	text[ ..name_at ].split( ' ' ).filter_map( |pair| {
+		let mut pair = pair.splitn( 2, '=' );
+		match ( pair.next( ), pair.next( ) ) {
+			( Some( k ), Some( v ) ) if k == key => Some( v ),
+			_ => None
+		}
+	} ).next( )
+}
+
+fn number<T: ::alloc::str::FromStr>( line: &[u8], key: &str ) -> Option<T> {
+	field( line, key ).and_then( |value| value.parse( ).ok( ) )
+}
+
+fn hex( line: &[u8], key: &str ) -> Option<u32> {
+	field( line, key ).and_then( |value| u32::from_str_radix( value, 16 ).ok( ) )
+}
+
+fn decode_part( s: &[u8] ) -> Result<Part, DecodeError> {
+
+	let mut lines = s.split( |&byte| byte == b'\n' ).map( |line| if line.ends_with( b"\r" ) { &line[ ..line.len( ) - 1 ] } else { line } );
+
+	let header = lines.by_ref( ).find( |line| line.starts_with( b"=ybegin " ) )
+		.ok_or( DecodeError::MissingLine { line: "=ybegin" } )?;
+	let name = match header.windows( 6 ).position( |window| window == b" name=" ) {
+		Some( at ) => String::from_utf8_lossy( &header[ at + 6..] ).into_owned( ),
+		None => String::new( ),
+	};
+	let size = number( header, "size" ).ok_or( DecodeError::MissingLine { line: "=ybegin" } )?;
+	let part = number( header, "part" );
+	let total = number( header, "total" );
+
+	let mut range = None;
+	let mut bytes = Vec::new( );
+	let mut trailer = None;
+	for line in lines {
+		if line.starts_with( b"=ypart " ) {
+			range = match ( number( line, "begin" ), number( line, "end" ) ) {
+				( Some( begin ), Some( end ) ) => Some( ( begin, end ) ),
+				_ => return Err( DecodeError::MissingLine { line: "=ypart" } ),
+			};
+			continue;
+		}
+		if line.starts_with( b"=yend" ) {
+			trailer = Some( line );
+			break;
+		}
+
+		let mut escaped = false;
+		for &byte in line {
+			if escaped {
+				bytes.push( byte.wrapping_sub( 64 ).wrapping_sub( 42 ) );
+				escaped = false;
+			} else if byte == b'=' {
+				escaped = true;
+			} else {
+				bytes.push( byte.wrapping_sub( 42 ) );
+			}
+		}
+	}
+
+	let trailer = trailer.ok_or( DecodeError::MissingLine { line: "=yend" } )?;
+	if part.is_some( ) && range.is_none( ) {
+		return Err( DecodeError::MissingLine { line: "=ypart" } );
+	}
+	if let Some( length ) = number::<u64>( trailer, "size" ) {
+		if length != bytes.len( ) as u64 {
+			return Err( DecodeError::InvalidLength { length: bytes.len( ) } );
+		}
+	}
+	if let Some( expected ) = hex( trailer, "pcrc32" ).or( if part.is_none( ) { hex( trailer, "crc32" ) } else { None } ) {
+		let actual = crc32( &bytes );
+		if expected != actual {
+			return Err( DecodeError::Checksum { expected, actual } );
+		}
+	}
+
+	Ok( Part { name, size, part, total, range, crc32: hex( trailer, "crc32" ), bytes } )
+}
+
+/// Decodes a single part yEnc file from `s`, checking its size and CRC-32
+///
+/// Text before the `=ybegin` line is skipped, so a whole article can be
+/// passed in. yEnc carries no permissions, so `mode` is always `None`.
+///
+/// # Examples
+///
+/// ```
+/// use mung::decode_yenc;
+/// let file = decode_yenc( b"=ybegin line=128 size=2 name=hi.txt\r\n\x92\x93\r\n=yend size=2\r\n" ).unwrap( );
+/// assert_eq!( file.name, "hi.txt" );
+/// assert_eq!( file.bytes, b"hi" );
+/// ```
+pub fn decode_yenc( s: &[u8] ) -> Result<Attachment, DecodeError> {
+	decode_yenc_parts( &[ s ] )
+}
+
+/// Reassembles a multi-part yEnc file from its parts, given in any order
+///
+/// Each part is checked against its own `pcrc32`, the whole against `crc32`
+/// and the size in the `=ybegin` line. A gap in the parts is reported as
+/// the first part number missing, and a part whose `=ybegin` gives another
+/// name or size than the first part's as `ForeignPart`.
+///
+/// # Examples
+///
+/// ```
+/// use mung::decode_yenc_parts;
+/// let second: &[u8] = b"=ybegin part=2 total=2 line=128 size=4 name=hi.txt\n=ypart begin=3 end=4\n\x92\x93\n=yend size=2 part=2\n";
+/// let first: &[u8] = b"=ybegin part=1 total=2 line=128 size=4 name=hi.txt\n=ypart begin=1 end=2\n\x92\x93\n=yend size=2 part=1\n";
+/// assert_eq!( decode_yenc_parts( &[ second, first ] ).unwrap( ).bytes, b"hihi" );
+/// ```
+pub fn decode_yenc_parts( parts: &[&[u8]] ) -> Result<Attachment, DecodeError> {
+
+	let mut decoded = parts.iter( ).map( |part| decode_part( part ) ).collect::<Result<Vec<Part>, DecodeError>>( )?;
+	if decoded.is_empty( ) {
+		return Err( DecodeError::MissingLine { line: "=ybegin" } );
+	}
+	decoded.sort_by_key( |part| part.range.map( |( begin, _ )| begin ).unwrap_or( 1 ) );
+
+	let size = decoded[ 0 ].size;
+	for ( i, part ) in decoded.iter( ).enumerate( ).skip( 1 ) {
+		if part.size != size || part.name != decoded[ 0 ].name {
+			return Err( DecodeError::ForeignPart { part: part.part.unwrap_or( i as u32 + 1 ) } );
+		}
+	}
+	// The header is not to be trusted with an allocation; the parts themselves are
+	let decoded_length: usize = decoded.iter( ).map( |part| part.bytes.len( ) ).sum( );
+	let mut bytes = Vec::with_capacity( decoded_length.min( size as usize ) );
+	for ( i, part ) in decoded.iter( ).enumerate( ) {
+		let ( begin, end ) = part.range.unwrap_or( ( 1, part.bytes.len( ) as u64 ) );
+		if begin != bytes.len( ) as u64 + 1 {
+			return Err( DecodeError::MissingPart { part: i as u32 + 1 } );
+		}
+		if end.checked_add( 1 ).and_then( |after| after.checked_sub( begin ) ) != Some( part.bytes.len( ) as u64 ) {
+			return Err( DecodeError::InvalidLength { length: part.bytes.len( ) } );
+		}
+		bytes.extend_from_slice( &part.bytes );
+	}
+
+	if bytes.len( ) as u64 != size {
+		let total = decoded[ 0 ].total.unwrap_or( 0 );
+		if decoded.len( ) < total as usize || decoded[ 0 ].part.is_some( ) {
+			return Err( DecodeError::MissingPart { part: decoded.len( ) as u32 + 1 } );
+		}
+		return Err( DecodeError::InvalidLength { length: bytes.len( ) } );
+	}
+	if let Some( expected ) = decoded.iter( ).filter_map( |part| part.crc32 ).next( ) {
+		let actual = crc32( &bytes );
+		if expected != actual {
+			return Err( DecodeError::Checksum { expected, actual } );
+		}
+	}
+
+	let name = decoded.swap_remove( 0 ).name;
+	Ok( Attachment { name, mode: None, bytes } )
+}
+
+
+#[cfg( test )]
+mod tests {
+	use super::*;
+
+	const PLAIN: &[u8] = b"hello yenc \x00\x0a\x0d=\xd6\xe0";
+
+	#[test]
+	fn test_crc32( ) {
+		assert_eq!( crc32( b"" ),					0 );
+		assert_eq!( crc32( b"123456789" ),			0xCBF4_3926 );
+		assert_eq!( crc32( PLAIN ),					0xDB8B_CEC2 );
+	}
+
+	#[test]
+	fn test_decode_yenc( ) {
+
+		let article = b"Subject: hello\r\n\r\n=ybegin line=128 size=17 name=hello world.bin\r\n\x92\x8f\x96\x96\x99J\xa3\x8f\x98\x8dJ*47g=@=J\r\n=yend size=17 crc32=db8bcec2\r\n";
+		let file = decode_yenc( article ).unwrap( );
+		assert_eq!( file.name,	"hello world.bin" );
+		assert_eq!( file.mode,	None );
+		assert_eq!( file.bytes,	PLAIN );
+
+		let corrupt = article.iter( ).map( |&byte| if byte == 0x99 { 0x9a } else { byte } ).collect::<Vec<u8>>( );
+		assert_eq!( decode_yenc( &corrupt ),		Err( DecodeError::Checksum { expected: 0xDB8B_CEC2, actual: crc32( b"hellp yenc \x00\x0a\x0d=\xd6\xe0" ) } ) );
+		assert_eq!( decode_yenc( &article[ ..article.len( ) - 35 ] ),	Err( DecodeError::MissingLine { line: "=yend" } ) );
+		assert_eq!( decode_yenc( b"plain text" ),	Err( DecodeError::MissingLine { line: "=ybegin" } ) );
+	}
+
+	#[test]
+	fn test_decode_yenc_parts( ) {
+
+		let first: &[u8] = b"=ybegin part=1 total=2 line=128 size=17 name=hello.bin\n=ypart begin=1 end=8\n\x92\x8f\x96\x96\x99J\xa3\x8f\n=yend size=8 part=1 pcrc32=047ba495\n";
+		let second: &[u8] = b"=ybegin part=2 total=2 line=128 size=17 name=hello.bin\n=ypart begin=9 end=17\n\x98\x8dJ*47g=@=J\n=yend size=9 part=2 pcrc32=0070e602 crc32=db8bcec2\n";
+
+		assert_eq!( decode_yenc_parts( &[ first, second ] ).map( |file| file.bytes ),	Ok( PLAIN.to_vec( ) ) );
+		assert_eq!( decode_yenc_parts( &[ second, first ] ).map( |file| file.name ),	Ok( "hello.bin".to_string( ) ) );
+		assert_eq!( decode_yenc_parts( &[ first ] ),		Err( DecodeError::MissingPart { part: 2 } ) );
+		assert_eq!( decode_yenc_parts( &[ second ] ),		Err( DecodeError::MissingPart { part: 1 } ) );
+		assert_eq!( decode_yenc( first ),					Err( DecodeError::MissingPart { part: 2 } ) );
+
+		let mut damaged = second.to_vec( );
+		let at = damaged.windows( 3 ).position( |window| window == b"47g" ).unwrap( );
+		damaged[ at + 2 ] = b'h';
+		assert_eq!( decode_yenc_parts( &[ first, &damaged ] ).map( |file| file.bytes ),	Err( DecodeError::Checksum { expected: 0x0070_E602, actual: crc32( b"nc \x00\x0a\x0d>\xd6\xe0" ) } ) );
+	}
+
+	#[test]
+	fn test_decode_yenc_hostile_headers( ) {
+
+		// Neither a size beyond memory nor a backwards part may panic
+		assert_eq!( decode_yenc( b"=ybegin line=128 size=18446744073709551615 name=huge\n\x92\x93\n=yend\n" ),	Err( DecodeError::InvalidLength { length: 2 } ) );
+		assert_eq!( decode_yenc( b"=ybegin part=1 line=128 size=2 name=hi\n=ypart begin=1 end=0\n\x92\x93\n=yend size=2 part=1\n" ),	Err( DecodeError::InvalidLength { length: 2 } ) );
+		let first: &[u8] = b"=ybegin part=1 total=2 line=128 size=4 name=hi\n=ypart begin=1 end=2\n\x92\x93\n=yend size=2 part=1\n";
+		let backwards: &[u8] = b"=ybegin part=2 total=2 line=128 size=4 name=hi\n=ypart begin=3 end=1\n\x92\x93\n=yend size=2 part=2\n";
+		let endless: &[u8] = b"=ybegin part=2 total=2 line=128 size=4 name=hi\n=ypart begin=3 end=18446744073709551615\n\x92\x93\n=yend size=2 part=2\n";
+		assert_eq!( decode_yenc_parts( &[ first, backwards ] ),	Err( DecodeError::InvalidLength { length: 2 } ) );
+		assert_eq!( decode_yenc_parts( &[ first, endless ] ),	Err( DecodeError::InvalidLength { length: 2 } ) );
+
+		// Parts of some other file, with the same part numbers
+		let resized: &[u8] = b"=ybegin part=2 total=2 line=128 size=5 name=hi\n=ypart begin=3 end=4\n\x92\x93\n=yend size=2 part=2\n";
+		let renamed: &[u8] = b"=ybegin part=2 total=2 line=128 size=4 name=ho\n=ypart begin=3 end=4\n\x92\x93\n=yend size=2 part=2\n";
+		assert_eq!( decode_yenc_parts( &[ first, resized ] ),	Err( DecodeError::ForeignPart { part: 2 } ) );
+		assert_eq!( decode_yenc_parts( &[ renamed, first ] ),	Err( DecodeError::ForeignPart { part: 2 } ) );
+		assert_eq!( DecodeError::ForeignPart { part: 2 }.to_string( ),	"part 2 belongs to another file" );
+	}
+}